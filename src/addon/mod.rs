@@ -1,9 +1,21 @@
+use crate::api::apply_network_settings;
 use crate::api::gw2::fetch_map_names_thread;
-use crate::config::{config_dir, migrate_configs, Config};
+use crate::api::kp::refresh::refresh_kp_on_unload;
+use crate::combat::{handle_combat_event, CombatData};
+use crate::state::pending_refresh::PendingRefresh;
+use crate::state::scheduling_pause::SchedulingPause;
+use crate::config::reload::save_config_if_changed;
+use crate::config::{config_dir, Config};
+use crate::context::scheduled_refresh::ScheduledRefresh;
 use crate::context::{init_context, Context};
+use crate::lease::{lease_dir, release_lease};
+use crate::state::State;
+use crate::sync::merge_from_sync_folder;
 use crate::thread::{background_thread, copy_kp_id_to_clipboard};
+use chrono::{DateTime, Utc};
 use function_name::named;
-use log::info;
+use log::{debug, info};
+use nexus::alert::send_alert;
 use nexus::event::event_raise_notification;
 use nexus::{event_subscribe, keybind_handler};
 use nexus::gui::{register_render, RenderType};
@@ -29,6 +41,7 @@ pub struct MultithreadedAddon {
 #[derive(Debug, Default)]
 pub struct Addon {
     pub config: Config,
+    pub state: State,
     pub context: Context,
 }
 
@@ -41,6 +54,87 @@ impl Addon {
             .unwrap()
     }
 
+    pub fn current_character(&self) -> Option<&String> {
+        self.context
+            .identity
+            .as_ref()
+            .map(|identity| &identity.name)
+    }
+
+    pub fn scheduling_enabled_for_character(&self) -> bool {
+        self.current_character().map_or(true, |character| {
+            !self
+                .config
+                .scheduling_disabled_characters
+                .contains(character)
+        })
+    }
+
+    pub fn scheduling_paused(&self) -> bool {
+        self.state
+            .scheduling_pause
+            .as_ref()
+            .is_some_and(|pause| pause.is_active())
+    }
+
+    #[named]
+    pub fn pause_scheduling(&mut self, pause: SchedulingPause) {
+        info!("[{}] scheduling {}", function_name!(), pause);
+        self.state.scheduling_pause = Some(pause);
+    }
+
+    #[named]
+    pub fn resume_scheduling(&mut self) {
+        info!("[{}] scheduling resumed", function_name!());
+        self.state.scheduling_pause = None;
+        if self.state.refresh_missed_while_paused {
+            self.state.refresh_missed_while_paused = false;
+            self.context.scheduled_refresh = Some(ScheduledRefresh::OnTime(Utc::now()));
+            send_alert("Refresh scheduling resumed, running missed refresh");
+        }
+    }
+
+    pub fn automatic_scheduling_enabled(&self) -> bool {
+        self.config.scheduling_rules_enabled && self.scheduling_enabled_for_character()
+    }
+
+    #[named]
+    pub fn schedule_refresh(&mut self, date: DateTime<Utc>) -> bool {
+        if !self.automatic_scheduling_enabled() {
+            debug!(
+                "[{}] scheduling disabled for current character",
+                function_name!()
+            );
+            return false;
+        }
+        let current = self.context.scheduled_refresh.take();
+        self.context.scheduled_refresh = Some(ScheduledRefresh::keep_later(current, date));
+        true
+    }
+
+    pub fn persist_scheduled_refresh(&mut self) {
+        if self.context.scheduled_refresh.is_some() {
+            self.state.refresh_on_next_load = true;
+        }
+        if self.context.pending_refresh_restored {
+            let account = self.config.kp_identifiers.main_id.clone();
+            self.state.pending_refresh = self
+                .context
+                .scheduled_refresh
+                .clone()
+                .map(|refresh| PendingRefresh { refresh, account });
+        }
+    }
+
+    pub fn toggle_scheduling_pause(&mut self) {
+        if self.scheduling_paused() {
+            self.resume_scheduling();
+        } else {
+            self.pause_scheduling(SchedulingPause::UntilResumed);
+            send_alert("Refresh scheduling paused");
+        }
+    }
+
     pub fn threads() -> MutexGuard<'static, Vec<JoinHandle<()>>> {
         MULTITHREADED_ADDON
             .threads
@@ -56,10 +150,20 @@ impl Addon {
             if let Some(config) = Config::try_load() {
                 Addon::lock().config = config;
             }
+            Addon::lock().state = State::load();
+            merge_from_sync_folder(&mut Addon::lock());
         }
 
-        migrate_configs(&mut Addon::lock());
         init_context(&mut Addon::lock());
+        {
+            let mut addon = Addon::lock();
+            if addon.state.scheduling_pause == Some(SchedulingPause::NextGameStart) {
+                addon.resume_scheduling();
+            }
+            apply_network_settings(&addon.config.network);
+            addon.validate_config();
+            addon.report_config_issues();
+        }
 
         unsafe {
             event_subscribe!("EV_ACCOUNT_NAME" => std::ffi::c_char, |name| {
@@ -73,6 +177,13 @@ impl Addon {
                 }
             })
         }.revert_on_unload();
+        unsafe {
+            event_subscribe!("EV_ARCDPS_COMBATEVENT_SQUAD_RAW" => CombatData, |data| {
+                if let Some(data) = data {
+                    handle_combat_event(data);
+                }
+            })
+        }.revert_on_unload();
         event_raise_notification("EV_REQUEST_ACCOUNT_NAME");
         fetch_map_names_thread();
         background_thread();
@@ -103,6 +214,30 @@ impl Addon {
         )
             .revert_on_unload();
 
+        let pause_keybind_handler = keybind_handler!(|_id, is_release| {
+            if is_release {
+                Addon::lock().toggle_scheduling_pause();
+            }
+        });
+        register_keybind_with_string(
+            "Toggle refresh scheduling pause",
+            pause_keybind_handler,
+            "(null)",
+        )
+        .revert_on_unload();
+
+        let preset_keybind_handler = keybind_handler!(|_id, is_release| {
+            if is_release {
+                Addon::lock().cycle_preset();
+            }
+        });
+        register_keybind_with_string(
+            "Switch to next config preset",
+            preset_keybind_handler,
+            "(null)",
+        )
+        .revert_on_unload();
+
         info!("[load] kp_sync loaded");
     }
 
@@ -127,6 +262,18 @@ impl Addon {
                 None => break,
             }
         }
+        refresh_kp_on_unload();
+        {
+            let mut addon = Self::lock();
+            let _ = release_lease(
+                &lease_dir(),
+                &addon.config.kp_identifiers.main_id,
+                &addon.context.instance_id,
+            );
+            addon.persist_scheduled_refresh();
+            save_config_if_changed(&mut addon);
+            addon.state.save();
+        }
         info!("[{}] kp_sync unloaded", function_name!());
     }
 }
@@ -0,0 +1,106 @@
+use reqwest::StatusCode;
+use serde::de::StdError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NetworkErrorKind {
+    Timeout,
+    Connect,
+    Tls,
+    Other,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum ApiError {
+    Network {
+        kind: NetworkErrorKind,
+        message: String,
+    },
+    HttpStatus(u16),
+    Parse(String),
+    RateLimited,
+    Maintenance,
+}
+
+impl ApiError {
+    pub fn from_status(status: StatusCode, body: &str) -> Self {
+        match status {
+            StatusCode::TOO_MANY_REQUESTS => ApiError::RateLimited,
+            StatusCode::SERVICE_UNAVAILABLE => ApiError::Maintenance,
+            _ if status.is_server_error() && body.to_lowercase().contains("maintenance") => {
+                ApiError::Maintenance
+            }
+            _ => ApiError::HttpStatus(status.as_u16()),
+        }
+    }
+
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ApiError::Network { kind, .. } => *kind != NetworkErrorKind::Tls,
+            ApiError::HttpStatus(status) => *status >= 500,
+            ApiError::RateLimited | ApiError::Maintenance => true,
+            ApiError::Parse(_) => false,
+        }
+    }
+}
+
+impl From<&reqwest::Error> for ApiError {
+    fn from(error: &reqwest::Error) -> Self {
+        if error.is_decode() {
+            return ApiError::Parse(error.to_string());
+        }
+        if let Some(status) = error.status() {
+            return ApiError::from_status(status, "");
+        }
+        let kind = if error.is_timeout() {
+            NetworkErrorKind::Timeout
+        } else if caused_by_tls(error) {
+            NetworkErrorKind::Tls
+        } else if error.is_connect() {
+            NetworkErrorKind::Connect
+        } else {
+            NetworkErrorKind::Other
+        };
+        ApiError::Network {
+            kind,
+            message: error.to_string(),
+        }
+    }
+}
+
+// reqwest reports TLS failures as connect errors, the cause is only visible in the chain
+fn caused_by_tls(error: &dyn StdError) -> bool {
+    let mut source = error.source();
+    while let Some(err) = source {
+        let message = err.to_string().to_lowercase();
+        if message.contains("certificate") || message.contains("tls") {
+            return true;
+        }
+        source = err.source();
+    }
+    false
+}
+
+impl fmt::Display for NetworkErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NetworkErrorKind::Timeout => write!(f, "timed out"),
+            NetworkErrorKind::Connect => write!(f, "could not connect"),
+            NetworkErrorKind::Tls => write!(f, "TLS error"),
+            NetworkErrorKind::Other => write!(f, "network error"),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Network { kind, .. } => write!(f, "{}", kind),
+            ApiError::HttpStatus(status) => write!(f, "unexpected HTTP status {}", status),
+            ApiError::Parse(_) => write!(f, "unexpected response"),
+            ApiError::RateLimited => write!(f, "rate limited"),
+            ApiError::Maintenance => write!(f, "server under maintenance"),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::addon::Addon;
+use crate::api::error::ApiError;
 use crate::api::get_sync;
 use function_name::named;
 use log::info;
@@ -31,11 +32,21 @@ pub fn fetch_map_names_thread() {
                         .into_iter()
                         .map(|element| (element.id, element.name))
                         .collect();
-                    Addon::lock().context.ui.map_names = map_hashmap;
+                    let mut addon = Addon::lock();
+                    addon.context.ui.map_names = map_hashmap;
+                    addon.validate_config();
                 }
-                Err(_) => warn!("[{}] failed to fetch json", function_name!()),
+                Err(error) => warn!(
+                    "[{}] failed to fetch json: {}",
+                    function_name!(),
+                    ApiError::from(&error)
+                ),
             },
-            Err(_) => warn!("[{}] could not fetch map names", function_name!()),
+            Err(error) => warn!(
+                "[{}] could not fetch map names: {}",
+                function_name!(),
+                ApiError::from(&error)
+            ),
         }
         info!("[{}] ended", function_name!());
     }));
@@ -1,37 +1,131 @@
+use crate::api::error::ApiError;
 use crate::api::kp::kp_path;
-use crate::api::kp::kp_response::failure_reason::FailureReason;
+use crate::api::kp::kp_response::failure_reason::{CooldownSource, FailureReason};
+use crate::api::kp::kp_response::response_meta::ResponseMeta;
 use crate::api::kp::kp_response::KpResponse;
+use crate::api::kp::proofs::fetch_last_refresh;
 use crate::api::{get_sync, print_error_chain};
 use ::function_name::named;
-use log::{error, warn};
+use chrono::{DateTime, TimeDelta, Utc};
+use log::{error, info, warn};
 use regex::Regex;
+use reqwest::header::{HeaderMap, RETRY_AFTER};
 use std::time::Duration;
 
 const DEFAULT_RETRY_FREQUENCY: Duration = Duration::new(5 * 60, 0);
+const KP_REFRESH_COOLDOWN: Duration = Duration::new(60 * 60, 0);
+const RATE_LIMIT_RESET_HEADERS: &[&str] = &["ratelimit-reset", "x-ratelimit-reset"];
+
+pub type KpCooldown = (Duration, CooldownSource);
+
+pub fn cooldown_from_response(headers: &HeaderMap) -> Option<KpCooldown> {
+    let now = Utc::now();
+    if let Some(duration) =
+        header(headers, RETRY_AFTER.as_str()).and_then(|value| parse_retry_after(value, now))
+    {
+        return Some((duration, CooldownSource::RetryAfterHeader));
+    }
+    for name in RATE_LIMIT_RESET_HEADERS {
+        if let Some(duration) = header(headers, name).and_then(|value| parse_reset(value, now)) {
+            return Some((duration, CooldownSource::RateLimitHeader));
+        }
+    }
+    None
+}
 
 #[named]
-pub fn cooldown_request(kp_id: &String) -> KpResponse {
-    match get_sync(kp_path(kp_id)) {
+pub fn cooldown_request(kp_id: &String, meta: ResponseMeta) -> KpResponse {
+    if let Some(duration) = fetch_last_refresh(kp_id)
+        .and_then(|last_refresh| cooldown_from_last_refresh(last_refresh, Utc::now()))
+    {
+        return cooldown_response((duration, CooldownSource::LastRefreshField), meta);
+    }
+    match fetch_text(kp_path(kp_id)).and_then(extract_duration) {
+        Some(duration) => cooldown_response((duration, CooldownSource::PageText), meta),
+        None => {
+            warn!(
+                "[{}] Could not determine cooldown, using default",
+                function_name!()
+            );
+            default_cooldown_response(meta)
+        }
+    }
+}
+
+pub fn default_cooldown_response(meta: ResponseMeta) -> KpResponse {
+    cooldown_response((DEFAULT_RETRY_FREQUENCY, CooldownSource::Default), meta)
+}
+
+#[named]
+pub fn cooldown_response(cooldown: KpCooldown, meta: ResponseMeta) -> KpResponse {
+    let (duration, source) = cooldown;
+    info!(
+        "[{}] Cooldown of {}s determined by {}",
+        function_name!(),
+        duration.as_secs(),
+        source
+    );
+    KpResponse::Failure(FailureReason::RefreshCooldown(duration, source), meta)
+}
+
+#[named]
+fn fetch_text(url: String) -> Option<String> {
+    match get_sync(url) {
         Ok(response) => match response.text() {
-            Ok(html) => match extract_duration(html) {
-                Some(duration) => KpResponse::Failure(FailureReason::RefreshCooldown(duration)),
-                None => default_cooldown_response(),
-            },
-            _ => {
-                warn!("[{}] Could not get html", function_name!());
-                default_cooldown_response()
+            Ok(text) => Some(text),
+            Err(error) => {
+                warn!(
+                    "[{}] Could not read response: {}",
+                    function_name!(),
+                    ApiError::from(&error)
+                );
+                None
             }
         },
         Err(error) => {
-            error!("[{}] Unknown error: {}", function_name!(), error);
+            error!(
+                "[{}] Request failed: {}",
+                function_name!(),
+                ApiError::from(&error)
+            );
             print_error_chain(&error);
-            default_cooldown_response()
+            None
         }
     }
 }
 
-pub fn default_cooldown_response() -> KpResponse {
-    KpResponse::Failure(FailureReason::RefreshCooldown(DEFAULT_RETRY_FREQUENCY))
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers.get(name)?.to_str().ok()
+}
+
+fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(until(date.with_timezone(&Utc), now))
+}
+
+fn parse_reset(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim().parse::<u64>().ok()?;
+    if value > 1_000_000_000 {
+        let date = DateTime::from_timestamp(value as i64, 0)?;
+        Some(until(date, now))
+    } else {
+        Some(Duration::from_secs(value))
+    }
+}
+
+// the API has no cooldown field, the remaining time follows from the last refresh
+fn cooldown_from_last_refresh(last_refresh: DateTime<Utc>, now: DateTime<Utc>) -> Option<Duration> {
+    let available = last_refresh + TimeDelta::from_std(KP_REFRESH_COOLDOWN).ok()?;
+    let duration = until(available, now).min(KP_REFRESH_COOLDOWN);
+    (!duration.is_zero()).then_some(duration)
+}
+
+fn until(date: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (date - now).to_std().unwrap_or_default()
 }
 
 fn extract_duration(text: String) -> Option<Duration> {
@@ -47,3 +141,92 @@ fn extract_duration(text: String) -> Option<Duration> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use reqwest::header::HeaderValue;
+
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn retry_after_seconds() {
+        assert_eq!(
+            parse_retry_after(" 120 ", now()),
+            Some(Duration::from_secs(120))
+        );
+    }
+
+    #[test]
+    fn retry_after_http_date() {
+        assert_eq!(
+            parse_retry_after("Wed, 01 May 2024 12:05:00 GMT", now()),
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 01 May 2024 11:55:00 GMT", now()),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now()), None);
+    }
+
+    #[test]
+    fn reset_seconds_or_timestamp() {
+        assert_eq!(parse_reset("90", now()), Some(Duration::from_secs(90)));
+        let timestamp = (now().timestamp() + 600).to_string();
+        assert_eq!(
+            parse_reset(&timestamp, now()),
+            Some(Duration::from_secs(600))
+        );
+        assert_eq!(parse_reset("-5", now()), None);
+    }
+
+    #[test]
+    fn retry_after_takes_precedence_over_rate_limit_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("30"));
+        assert_eq!(
+            cooldown_from_response(&headers),
+            Some((Duration::from_secs(30), CooldownSource::RateLimitHeader))
+        );
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("60"));
+        assert_eq!(
+            cooldown_from_response(&headers),
+            Some((Duration::from_secs(60), CooldownSource::RetryAfterHeader))
+        );
+        assert_eq!(cooldown_from_response(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn cooldown_follows_last_refresh() {
+        let last_refresh = now() - TimeDelta::minutes(20);
+        assert_eq!(
+            cooldown_from_last_refresh(last_refresh, now()),
+            Some(Duration::from_secs(40 * 60))
+        );
+    }
+
+    #[test]
+    fn expired_or_future_last_refresh() {
+        let expired = now() - TimeDelta::minutes(90);
+        assert_eq!(cooldown_from_last_refresh(expired, now()), None);
+        let future = now() + TimeDelta::minutes(5);
+        assert_eq!(
+            cooldown_from_last_refresh(future, now()),
+            Some(KP_REFRESH_COOLDOWN)
+        );
+    }
+
+    #[test]
+    fn extracts_duration_from_page_text() {
+        let text = "Time until next refresh available is 12 minutes".to_string();
+        assert_eq!(
+            extract_duration(text),
+            Some(Duration::from_secs(12 * 60 + 30))
+        );
+        assert_eq!(extract_duration("nothing here".to_string()), None);
+    }
+}
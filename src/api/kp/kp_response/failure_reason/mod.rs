@@ -1,12 +1,23 @@
+use crate::api::error::ApiError;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::time::Duration;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum CooldownSource {
+    RetryAfterHeader,
+    RateLimitHeader,
+    LastRefreshField,
+    PageText,
+    Default,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum FailureReason {
     NotFound,
     NotAccessible,
-    RefreshCooldown(Duration),
-    Unknown,
+    RefreshCooldown(Duration, CooldownSource),
+    Api(ApiError),
 }
 
 impl fmt::Display for FailureReason {
@@ -14,8 +25,20 @@ impl fmt::Display for FailureReason {
         match self {
             FailureReason::NotFound => write!(f, "not found"),
             FailureReason::NotAccessible => write!(f, "not accessible"),
-            FailureReason::RefreshCooldown(_) => write!(f, "refreshed too recently"),
-            FailureReason::Unknown => write!(f, "unknown error occurred"),
+            FailureReason::RefreshCooldown(..) => write!(f, "refreshed too recently"),
+            FailureReason::Api(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl fmt::Display for CooldownSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CooldownSource::RetryAfterHeader => write!(f, "Retry-After header"),
+            CooldownSource::RateLimitHeader => write!(f, "rate limit header"),
+            CooldownSource::LastRefreshField => write!(f, "last_refresh in API response"),
+            CooldownSource::PageText => write!(f, "proof page text"),
+            CooldownSource::Default => write!(f, "default"),
         }
     }
 }
@@ -1,23 +1,39 @@
 pub mod failure_reason;
+pub mod response_meta;
 
 use crate::api::kp::kp_response::failure_reason::FailureReason;
+use crate::api::kp::kp_response::response_meta::ResponseMeta;
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum KpResponse {
     Unavailable,
-    Success,
-    InvalidId(String),
-    Failure(FailureReason),
+    Success(ResponseMeta),
+    InvalidId(String, ResponseMeta),
+    Failure(FailureReason, ResponseMeta),
+    HandledByOtherClient,
+}
+
+impl KpResponse {
+    pub fn meta(&self) -> Option<&ResponseMeta> {
+        match self {
+            KpResponse::Success(meta)
+            | KpResponse::InvalidId(_, meta)
+            | KpResponse::Failure(_, meta) => Some(meta),
+            KpResponse::Unavailable | KpResponse::HandledByOtherClient => None,
+        }
+    }
 }
 
 impl fmt::Display for KpResponse {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             KpResponse::Unavailable => write!(f, "not refreshed recently"),
-            KpResponse::Success => write!(f, "refresh successful"),
-            KpResponse::Failure(reason) => write!(f, "failed ({})", reason),
-            KpResponse::InvalidId(kp_id) => {
+            KpResponse::Success(_) => write!(f, "refresh successful"),
+            KpResponse::Failure(reason, _) => write!(f, "failed ({})", reason),
+            KpResponse::HandledByOtherClient => write!(f, "handled by another client"),
+            KpResponse::InvalidId(kp_id, _) => {
                 write!(f, "invalid config (KP id \"{}\" not found)", kp_id)
             }
         }
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Instant;
+
+const BODY_EXCERPT_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ResponseMeta {
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub date: DateTime<Utc>,
+    pub body_excerpt: Option<String>,
+}
+
+pub struct RequestTimer {
+    started: Instant,
+    date: DateTime<Utc>,
+}
+
+impl RequestTimer {
+    pub fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            date: Utc::now(),
+        }
+    }
+
+    pub fn finish(&self, status: Option<StatusCode>, body: Option<&str>) -> ResponseMeta {
+        ResponseMeta {
+            status: status.map(|status| status.as_u16()),
+            latency_ms: self.started.elapsed().as_millis() as u64,
+            date: self.date,
+            body_excerpt: body.map(excerpt),
+        }
+    }
+}
+
+fn excerpt(body: &str) -> String {
+    let body = body.trim();
+    match body.char_indices().nth(BODY_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}..", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+impl fmt::Display for ResponseMeta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "HTTP {} in {} ms", status, self.latency_ms),
+            None => write!(f, "no response after {} ms", self.latency_ms),
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use crate::addon::Addon;
+use crate::api::error::ApiError;
 use crate::api::kp::kp_path;
 use crate::api::kp::kp_response::KpResponse;
 use crate::api::kp::refresh::request::refresh_kp_request;
 use crate::api::{get_sync, print_error_chain};
+use crate::config::valid_kp_id;
 use ::function_name::named;
 use log::{debug, error, info, warn};
 use nexus::alert::send_alert;
@@ -22,10 +24,10 @@ pub fn fetch_linked_ids_thread() {
         let ids = fetch_linked_ids(&kp_id);
         let mut addon = Addon::lock();
         if ids.is_empty() {
-            addon.config.kp_identifiers.linked_ids = None;
+            addon.state.linked_ids = None;
             addon.context.ui.errors.linked_ids = true;
         } else {
-            addon.config.kp_identifiers.linked_ids = Some(ids);
+            addon.state.linked_ids = Some(ids);
         }
         info!("[{}] ended", function_name!());
     }));
@@ -41,7 +43,7 @@ pub fn refresh_linked_kp(linked_id: &String) -> KpResponse {
     let kp_response = refresh_kp_request(linked_id, false);
     debug!("[{}] Linked kp response: {}", function_name!(), kp_response);
     if Addon::lock().config.notifications.notify_failure_linked
-        && matches!(kp_response, KpResponse::Failure(_))
+        && matches!(kp_response, KpResponse::Failure(..))
     {
         send_alert(format!(
             "Linked Killproof account {} could not be refreshed",
@@ -62,7 +64,11 @@ pub fn fetch_linked_ids(kp_id: &String) -> Vec<String> {
             }
         },
         Err(error) => {
-            error!("[{}] Unknown error: {}", function_name!(), error);
+            error!(
+                "[{}] Request failed: {}",
+                function_name!(),
+                ApiError::from(&error)
+            );
             print_error_chain(&error);
             vec![]
         }
@@ -77,7 +83,16 @@ fn extract_linked_ids(html: String) -> Vec<String> {
     if let Some(link) = link {
         let paragraph = link.parent();
         for a_tag in paragraph.unwrap().find(Name("a")) {
-            linked_accounts.push(a_tag.text());
+            let linked_id = a_tag.text();
+            if valid_kp_id(&linked_id) {
+                linked_accounts.push(linked_id);
+            } else {
+                warn!(
+                    "[{}] Skipped invalid linked id: {}",
+                    function_name!(),
+                    linked_id
+                );
+            }
         }
     }
     debug!(
@@ -87,3 +102,20 @@ fn extract_linked_ids(html: String) -> Vec<String> {
     );
     linked_accounts
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_invalid_linked_ids() {
+        let html = r#"<html><body><p><i class="fa fa-link"></i>
+            <a href="/proof/Alt.5678">Alt.5678</a>
+            <a href="/proof/x">not valid!</a>
+            <a href="/proof/abc123">abc123</a></p></body></html>"#;
+        assert_eq!(
+            extract_linked_ids(html.to_string()),
+            vec!["Alt.5678".to_string(), "abc123".to_string()]
+        );
+    }
+}
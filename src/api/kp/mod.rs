@@ -1,6 +1,7 @@
 mod cooldown;
 pub mod kp_response;
 pub mod linked_ids;
+pub mod proofs;
 pub mod refresh;
 
 const KP_URL: &str = "https://killproof.me";
@@ -8,3 +9,7 @@ const KP_URL: &str = "https://killproof.me";
 fn kp_path(kp_id: &String) -> String {
     format!("{}/proof/{}", KP_URL, kp_id)
 }
+
+fn kp_api_path(kp_id: &String) -> String {
+    format!("{}/api/kp/{}?lang=en", KP_URL, kp_id)
+}
@@ -0,0 +1,105 @@
+use crate::api::error::ApiError;
+use crate::api::kp::kp_api_path;
+use crate::api::{get_sync, print_error_chain};
+use ::function_name::named;
+use chrono::{DateTime, Utc};
+use log::{error, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize)]
+struct ProofsResponse {
+    #[serde(default)]
+    last_refresh: Option<String>,
+    #[serde(default)]
+    killproofs: Vec<Proof>,
+    #[serde(default)]
+    tokens: Vec<Proof>,
+    #[serde(default)]
+    coffers: Vec<Proof>,
+}
+
+#[derive(Deserialize)]
+struct Proof {
+    name: String,
+    #[serde(default)]
+    amount: u32,
+}
+
+pub fn fetch_proofs(kp_id: &String) -> Option<HashMap<String, u32>> {
+    fetch_proofs_response(kp_id).map(|proofs| {
+        proofs
+            .killproofs
+            .into_iter()
+            .chain(proofs.tokens)
+            .chain(proofs.coffers)
+            .map(|proof| (proof.name, proof.amount))
+            .collect()
+    })
+}
+
+pub fn fetch_last_refresh(kp_id: &String) -> Option<DateTime<Utc>> {
+    last_refresh(&fetch_proofs_response(kp_id)?)
+}
+
+fn last_refresh(proofs: &ProofsResponse) -> Option<DateTime<Utc>> {
+    let date = DateTime::parse_from_rfc3339(proofs.last_refresh.as_deref()?).ok()?;
+    Some(date.with_timezone(&Utc))
+}
+
+#[named]
+fn fetch_proofs_response(kp_id: &String) -> Option<ProofsResponse> {
+    match get_sync(kp_api_path(kp_id)) {
+        Ok(response) => match response.json::<ProofsResponse>() {
+            Ok(proofs) => Some(proofs),
+            Err(error) => {
+                warn!(
+                    "[{}] Could not parse proofs: {}",
+                    function_name!(),
+                    ApiError::from(&error)
+                );
+                None
+            }
+        },
+        Err(error) => {
+            error!(
+                "[{}] Request failed: {}",
+                function_name!(),
+                ApiError::from(&error)
+            );
+            print_error_chain(&error);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn parse(body: &str) -> ProofsResponse {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn reads_last_refresh() {
+        let proofs = parse(
+            r#"{"last_refresh": "2024-05-01T11:30:00.000Z", "killproofs": [{"name": "Legendary Insight", "amount": 8}]}"#,
+        );
+        assert_eq!(
+            last_refresh(&proofs),
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 11, 30, 0).unwrap())
+        );
+        assert_eq!(proofs.killproofs[0].amount, 8);
+    }
+
+    #[test]
+    fn missing_or_invalid_last_refresh_is_ignored() {
+        assert_eq!(last_refresh(&parse(r#"{"killproofs": []}"#)), None);
+        assert_eq!(
+            last_refresh(&parse(r#"{"last_refresh": "yesterday"}"#)),
+            None
+        );
+    }
+}
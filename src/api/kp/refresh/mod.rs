@@ -1,14 +1,18 @@
 pub mod request;
+pub mod retry;
 
 use crate::addon::Addon;
-use crate::api::kp::kp_response::failure_reason::FailureReason;
+use crate::api::error::ApiError;
+use crate::api::kp::kp_response::failure_reason::{CooldownSource, FailureReason};
 use crate::api::kp::kp_response::KpResponse;
 use crate::api::kp::linked_ids::refresh_linked_kp;
-use crate::api::kp::refresh::request::refresh_kp_request;
+use crate::api::kp::refresh::request::{refresh_kp_request, refresh_kp_request_with_timeout};
+use crate::api::kp::refresh::retry::{jitter, record_failure};
 use crate::context::scheduled_refresh::ScheduledRefresh;
+use crate::lease::{acquire_lease, lease_dir, LeaseStatus, LINKED_LEASE_TTL_SEC};
 use crate::render::countdown_str;
 use ::function_name::named;
-use chrono::{Local, TimeDelta};
+use chrono::{TimeDelta, Utc};
 use log::{debug, info, warn};
 use nexus::alert::send_alert;
 use std::ops::Add;
@@ -26,6 +30,13 @@ fn cant_start_refresh() -> bool {
         warn!("[{}] refresh is already in progress", function_name!());
         return true;
     }
+    if Addon::lock().context.other_client_lease.is_some() {
+        warn!(
+            "[{}] refresh is handled by another client",
+            function_name!()
+        );
+        return true;
+    }
     false
 }
 
@@ -36,7 +47,13 @@ pub fn refresh_kp_thread() {
         if cant_start_refresh() {
             return;
         }
-        Addon::lock().context.refresh_in_progress = true;
+        {
+            let mut addon = Addon::lock();
+            addon.context.refresh_in_progress = true;
+            if let Some(session) = addon.context.session.as_mut() {
+                session.refresh_attempts += 1;
+            }
+        }
 
         let kp_id = Addon::lock().config.kp_identifiers.main_id.clone();
         Addon::lock().context.linked_kp_responses = vec![];
@@ -44,12 +61,13 @@ pub fn refresh_kp_thread() {
         let main_kp_response = refresh_kp_request(&kp_id, true);
         handle_main_kp_response(main_kp_response);
 
-        let linked_ids = Addon::lock().config.kp_identifiers.linked_ids.clone();
+        let linked_ids = Addon::lock().state.linked_ids.clone();
 
         if let Some(linked_ids) = linked_ids {
             let mut kp_responses: Vec<(String, KpResponse)> = Vec::new();
+            let instance_id = Addon::lock().context.instance_id.clone();
             for linked_id in linked_ids {
-                let kp_response = refresh_linked_kp(&linked_id);
+                let kp_response = refresh_linked_kp_with_lease(&linked_id, &instance_id);
                 kp_responses.push((linked_id, kp_response));
             }
             Addon::lock().context.linked_kp_responses = kp_responses;
@@ -58,42 +76,194 @@ pub fn refresh_kp_thread() {
         info!("[{}] refresh status updated", function_name!());
     }));
 }
-fn handle_main_kp_response(main_kp_response: KpResponse) {
+#[named]
+fn refresh_linked_kp_with_lease(linked_id: &String, instance_id: &str) -> KpResponse {
+    let dir = lease_dir();
+    let ttl = TimeDelta::seconds(LINKED_LEASE_TTL_SEC);
+    match acquire_lease(&dir, linked_id, instance_id, ttl, None, None) {
+        Ok(LeaseStatus::Acquired) => {
+            // the lease is left to expire, so other clients do not refresh the id right after
+            let kp_response = refresh_linked_kp(linked_id);
+            if let KpResponse::Failure(FailureReason::RefreshCooldown(duration, _), _) =
+                &kp_response
+            {
+                let cooldown = TimeDelta::from_std(*duration).unwrap_or(ttl).max(ttl);
+                let _ = acquire_lease(&dir, linked_id, instance_id, cooldown, None, None);
+            }
+            kp_response
+        }
+        Ok(LeaseStatus::HeldBy(lease)) => {
+            info!(
+                "[{}] {} is handled by client {}",
+                function_name!(),
+                linked_id,
+                lease.owner
+            );
+            KpResponse::HandledByOtherClient
+        }
+        Err(err) => {
+            warn!(
+                "[{}] {} skipped, lease unavailable: {}",
+                function_name!(),
+                linked_id,
+                err
+            );
+            KpResponse::Unavailable
+        }
+    }
+}
+
+#[named]
+pub fn refresh_kp_on_unload() {
+    let (kp_id, time_budget_ms) = {
+        let addon = Addon::lock();
+        if !addon.config.unload_refresh.enabled
+            || addon.context.scheduled_refresh.is_none()
+            || addon.scheduling_paused()
+            || addon.context.other_client_lease.is_some()
+            || !addon.config.valid()
+        {
+            return;
+        }
+        (
+            addon.config.kp_identifiers.main_id.clone(),
+            addon.config.unload_refresh.time_budget_ms,
+        )
+    };
+
+    info!("[{}] attempting pending refresh", function_name!());
+    let timeout = Duration::from_millis(time_budget_ms as u64);
+    let kp_response = refresh_kp_request_with_timeout(&kp_id, timeout);
     let mut addon = Addon::lock();
-    match main_kp_response {
-        KpResponse::Success => handle_success_kp_response(&mut addon),
-        KpResponse::Failure(FailureReason::RefreshCooldown(duration)) => {
-            handle_failure_cooldown_kp_response(&mut addon, duration)
+    match kp_response {
+        KpResponse::Success(_) => {
+            info!("[{}] pending refresh done", function_name!());
+            addon.state.last_refresh_date = Some(Utc::now());
+            addon.state.refresh_on_next_load = false;
+            addon.context.scheduled_refresh = None;
         }
-        KpResponse::InvalidId(_) => handle_invalid_id_kp_response(&mut addon),
         _ => {
+            info!(
+                "[{}] pending refresh failed ({}), refreshing on next load",
+                function_name!(),
+                kp_response
+            );
+            addon.state.refresh_on_next_load = true;
+        }
+    }
+}
+
+fn handle_main_kp_response(main_kp_response: KpResponse) {
+    let mut addon = Addon::lock();
+    match &main_kp_response {
+        KpResponse::Success(_) => handle_success_kp_response(&mut addon),
+        KpResponse::Failure(FailureReason::RefreshCooldown(duration, source), _) => {
+            handle_failure_cooldown_kp_response(&mut addon, *duration, *source)
+        }
+        KpResponse::InvalidId(..) => handle_invalid_id_kp_response(&mut addon),
+        KpResponse::Failure(FailureReason::Api(error), _)
+            if error.is_transient() && addon.config.retry.enabled =>
+        {
+            handle_transient_failure_kp_response(&mut addon, error)
+        }
+        KpResponse::Failure(reason, _) => {
+            addon.context.retry_attempts = None;
             if addon.config.notifications.notify_failure {
-                send_alert("Killproof could not be refreshed due to unknown error");
+                send_alert(format!("Killproof could not be refreshed: {}", reason));
             }
         }
+        KpResponse::Unavailable | KpResponse::HandledByOtherClient => {}
     }
     addon.context.main_kp_response = main_kp_response;
 }
 
 fn handle_invalid_id_kp_response(addon: &mut MutexGuard<Addon>) {
     addon.context.scheduled_refresh = None;
-    addon.config.kp_identifiers.linked_ids = None;
+    addon.context.retry_attempts = None;
+    addon.state.linked_ids = None;
     if addon.config.notifications.notify_failure {
         send_alert("Killproof could not be refreshed due to invalid configuration");
     }
 }
 
 fn handle_success_kp_response(addon: &mut MutexGuard<Addon>) {
-    addon.config.last_refresh_date = Some(Local::now());
+    addon.state.last_refresh_date = Some(Utc::now());
     addon.context.scheduled_refresh = None;
+    addon.context.retry_attempts = None;
+    if let Some(session) = addon.context.session.as_mut() {
+        session.refreshed = true;
+    }
+    let kill_names = addon.context.take_unrefreshed_kill_names();
     if addon.config.notifications.notify_success {
-        send_alert("Killproof refreshed successfully");
+        if kill_names.is_empty() {
+            send_alert("Killproof refreshed successfully");
+        } else {
+            send_alert(format!(
+                "Killproof refreshed successfully (killed: {})",
+                kill_names.join(", ")
+            ));
+        }
+    }
+}
+
+#[named]
+fn handle_transient_failure_kp_response(addon: &mut MutexGuard<Addon>, error: &ApiError) {
+    let retry = addon.config.retry.clone();
+    let now = Utc::now();
+    match record_failure(&mut addon.context.retry_attempts, &retry, now, jitter()) {
+        Some(delay) => {
+            addon.context.scheduled_refresh = Some(ScheduledRefresh::OnTime(now.add(delay)));
+            let failed = addon
+                .context
+                .retry_attempts
+                .as_ref()
+                .map_or(1, |attempts| attempts.failed);
+            debug!(
+                "[{}] Attempt {} failed ({}), retrying in {:?}s",
+                function_name!(),
+                failed,
+                error,
+                delay.as_secs()
+            );
+            if addon.config.notifications.notify_retry {
+                send_alert(format!(
+                    "Killproof could not be reached ({}), attempt {} of {} in {}",
+                    error,
+                    failed + 1,
+                    retry.max_attempts,
+                    countdown_str(TimeDelta::seconds(delay.as_secs() as i64))
+                ));
+            }
+        }
+        None => {
+            info!("[{}] giving up after failure: {}", function_name!(), error);
+            if addon.config.notifications.notify_failure {
+                send_alert(format!(
+                    "Killproof could not be refreshed ({}), gave up retrying",
+                    error
+                ));
+            }
+        }
+    }
+}
+
+// Falling back to the default cooldown means kp.me changed, only worth telling once per session.
+fn take_cooldown_fallback_alert(source: CooldownSource, alerted: &mut bool) -> bool {
+    if source != CooldownSource::Default || *alerted {
+        return false;
     }
+    *alerted = true;
+    true
 }
 
 #[named]
-fn handle_failure_cooldown_kp_response(addon: &mut MutexGuard<Addon>, duration: Duration) {
-    addon.context.scheduled_refresh = Some(ScheduledRefresh::OnTime(Local::now().add(duration)));
+fn handle_failure_cooldown_kp_response(
+    addon: &mut MutexGuard<Addon>,
+    duration: Duration,
+    source: CooldownSource,
+) {
+    addon.context.retry_attempts = None;
+    addon.context.scheduled_refresh = Some(ScheduledRefresh::OnTime(Utc::now().add(duration)));
     debug!(
         "[{}] Failed to refresh, retrying in {:?}s",
         function_name!(),
@@ -105,4 +275,41 @@ fn handle_failure_cooldown_kp_response(addon: &mut MutexGuard<Addon>, duration:
             countdown_str(TimeDelta::seconds(duration.as_secs() as i64))
         ));
     }
+    if take_cooldown_fallback_alert(source, &mut addon.context.cooldown_fallback_alerted) {
+        send_alert(
+            "Killproof refresh cooldown could not be determined, the addon may need an update",
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_cooldown_alerts_once() {
+        let mut alerted = false;
+        assert!(take_cooldown_fallback_alert(
+            CooldownSource::Default,
+            &mut alerted
+        ));
+        assert!(alerted);
+        assert!(!take_cooldown_fallback_alert(
+            CooldownSource::Default,
+            &mut alerted
+        ));
+    }
+
+    #[test]
+    fn determined_cooldown_does_not_alert() {
+        let mut alerted = false;
+        for source in [
+            CooldownSource::RetryAfterHeader,
+            CooldownSource::RateLimitHeader,
+            CooldownSource::PageText,
+        ] {
+            assert!(!take_cooldown_fallback_alert(source, &mut alerted));
+        }
+        assert!(!alerted);
+    }
 }
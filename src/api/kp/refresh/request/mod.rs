@@ -1,58 +1,126 @@
-use crate::api::kp::cooldown::{cooldown_request, default_cooldown_response};
+use crate::api::error::ApiError;
+use crate::api::kp::cooldown::{
+    cooldown_from_response, cooldown_request, cooldown_response, default_cooldown_response,
+};
 use crate::api::kp::kp_response::failure_reason::FailureReason;
+use crate::api::kp::kp_response::response_meta::{RequestTimer, ResponseMeta};
 use crate::api::kp::kp_response::KpResponse;
 use crate::api::kp::KP_URL;
-use crate::api::{get_sync, print_error_chain};
+use crate::api::{get_sync, get_sync_with_timeout, print_error_chain};
 use ::function_name::named;
-use log::error;
+use log::{debug, error};
 use reqwest::blocking::Response;
-use reqwest::{self, StatusCode};
+use reqwest::header::HeaderMap;
+use reqwest::{self, Error, StatusCode};
+use std::time::Duration;
 
-#[named]
 pub fn refresh_kp_request(kp_id: &String, fetch_cooldown: bool) -> KpResponse {
-    let path = refresh_path(kp_id);
-
-    match get_sync(path) {
-        Ok(response) => match response.status() {
-            StatusCode::OK => handle_ok_http_response(kp_id, response),
-            StatusCode::FORBIDDEN => KpResponse::Failure(FailureReason::NotAccessible),
-            StatusCode::NOT_FOUND => KpResponse::Failure(FailureReason::NotFound),
-            StatusCode::NOT_MODIFIED => handle_not_modified_http_response(kp_id, fetch_cooldown),
-            _ => {
-                error!(
-                    "[{}] Unknown status: {}",
-                    function_name!(),
-                    response.status()
-                );
-                KpResponse::Failure(FailureReason::Unknown)
+    send_refresh_request(kp_id, refresh_path(kp_id), None, fetch_cooldown)
+}
+
+pub fn refresh_kp_request_with_timeout(kp_id: &String, timeout: Duration) -> KpResponse {
+    send_refresh_request(kp_id, refresh_path(kp_id), Some(timeout), false)
+}
+
+pub(super) fn send_refresh_request(
+    kp_id: &String,
+    url: String,
+    timeout: Option<Duration>,
+    fetch_cooldown: bool,
+) -> KpResponse {
+    let timer = RequestTimer::start();
+    let result = match timeout {
+        Some(timeout) => get_sync_with_timeout(url, timeout),
+        None => get_sync(url),
+    };
+    handle_http_response(kp_id, result, timer, fetch_cooldown)
+}
+
+#[named]
+fn handle_http_response(
+    kp_id: &String,
+    result: Result<Response, Error>,
+    timer: RequestTimer,
+    fetch_cooldown: bool,
+) -> KpResponse {
+    let kp_response = kp_response_from_http(kp_id, result, timer, fetch_cooldown);
+    if let Ok(json) = serde_json::to_string(&kp_response) {
+        debug!("[{}] {}", function_name!(), json);
+    }
+    kp_response
+}
+
+#[named]
+fn kp_response_from_http(
+    kp_id: &String,
+    result: Result<Response, Error>,
+    timer: RequestTimer,
+    fetch_cooldown: bool,
+) -> KpResponse {
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = match response.text() {
+                Ok(body) => body,
+                Err(error) => {
+                    let meta = timer.finish(Some(status), None);
+                    let api_error = ApiError::from(&error);
+                    return KpResponse::Failure(FailureReason::Api(api_error), meta);
+                }
+            };
+            let meta = timer.finish(Some(status), Some(&body));
+            match status {
+                StatusCode::OK => handle_ok_http_response(kp_id, &body, meta),
+                StatusCode::FORBIDDEN => KpResponse::Failure(FailureReason::NotAccessible, meta),
+                StatusCode::NOT_FOUND => KpResponse::Failure(FailureReason::NotFound, meta),
+                StatusCode::NOT_MODIFIED | StatusCode::TOO_MANY_REQUESTS => {
+                    handle_cooldown_http_response(kp_id, &headers, fetch_cooldown, meta)
+                }
+                _ => {
+                    error!("[{}] Unexpected status: {}", function_name!(), status);
+                    let api_error = ApiError::from_status(status, &body);
+                    KpResponse::Failure(FailureReason::Api(api_error), meta)
+                }
             }
-        },
+        }
         Err(error) => {
-            error!("[{}] Unknown error: {}", function_name!(), error);
+            let api_error = ApiError::from(&error);
+            error!("[{}] Request failed: {}", function_name!(), api_error);
             print_error_chain(&error);
-            KpResponse::Failure(FailureReason::Unknown)
+            KpResponse::Failure(FailureReason::Api(api_error), timer.finish(None, None))
         }
     }
 }
 
-fn handle_ok_http_response(kp_id: &str, response: Response) -> KpResponse {
-    match response.text() {
-        Ok(html) => {
-            if html.contains(r#"status":"ok"#) {
-                KpResponse::Success
-            } else {
-                KpResponse::InvalidId(kp_id.to_string())
-            }
-        }
-        Err(_) => KpResponse::Failure(FailureReason::Unknown),
+fn handle_ok_http_response(kp_id: &str, body: &str, meta: ResponseMeta) -> KpResponse {
+    if body.contains(r#"status":"ok"#) {
+        KpResponse::Success(meta)
+    } else if body.to_lowercase().contains("maintenance") {
+        KpResponse::Failure(FailureReason::Api(ApiError::Maintenance), meta)
+    } else if body.trim().is_empty() {
+        let api_error = ApiError::Parse("empty refresh response".to_string());
+        KpResponse::Failure(FailureReason::Api(api_error), meta)
+    } else {
+        KpResponse::InvalidId(kp_id.to_string(), meta)
     }
 }
 
-fn handle_not_modified_http_response(kp_id: &String, fetch_cooldown: bool) -> KpResponse {
-    if fetch_cooldown {
-        cooldown_request(kp_id)
+// kp.me answers 304 when refreshed too recently, 429 when rate limited
+fn handle_cooldown_http_response(
+    kp_id: &String,
+    headers: &HeaderMap,
+    fetch_cooldown: bool,
+    meta: ResponseMeta,
+) -> KpResponse {
+    if let Some(cooldown) = cooldown_from_response(headers) {
+        cooldown_response(cooldown, meta)
+    } else if meta.status == Some(StatusCode::TOO_MANY_REQUESTS.as_u16()) {
+        KpResponse::Failure(FailureReason::Api(ApiError::RateLimited), meta)
+    } else if fetch_cooldown {
+        cooldown_request(kp_id, meta)
     } else {
-        default_cooldown_response()
+        default_cooldown_response(meta)
     }
 }
 
@@ -0,0 +1,217 @@
+use crate::config::retry::Retry;
+use chrono::{DateTime, TimeDelta, Utc};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryAttempts {
+    pub failed: u32,
+    pub first_failure_date: DateTime<Utc>,
+}
+
+pub fn record_failure(
+    attempts: &mut Option<RetryAttempts>,
+    retry: &Retry,
+    now: DateTime<Utc>,
+    jitter: f64,
+) -> Option<Duration> {
+    let current = attempts.get_or_insert(RetryAttempts {
+        failed: 0,
+        first_failure_date: now,
+    });
+    current.failed += 1;
+    let delay = next_retry_delay(retry, current, now, jitter);
+    if delay.is_none() {
+        *attempts = None;
+    }
+    delay
+}
+
+fn next_retry_delay(
+    retry: &Retry,
+    attempts: &RetryAttempts,
+    now: DateTime<Utc>,
+    jitter: f64,
+) -> Option<Duration> {
+    if attempts.failed >= retry.max_attempts {
+        return None;
+    }
+    let delay = backoff_delay(retry.base_delay_sec, attempts.failed, jitter);
+    let elapsed = (now - attempts.first_failure_date).max(TimeDelta::zero());
+    let total = elapsed + TimeDelta::from_std(delay).ok()?;
+    if total > TimeDelta::seconds(retry.max_total_delay_sec as i64) {
+        return None;
+    }
+    Some(delay)
+}
+
+fn backoff_delay(base_delay_sec: u32, failed: u32, jitter: f64) -> Duration {
+    let exponent = failed.saturating_sub(1).min(16);
+    let delay_ms = base_delay_sec as u64 * 1000 * 2u64.pow(exponent);
+    let jitter = jitter.clamp(0.0, 1.0);
+    Duration::from_millis(delay_ms / 2 + (delay_ms as f64 / 2.0 * jitter) as u64)
+}
+
+pub fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::error::{ApiError, NetworkErrorKind};
+    use crate::api::kp::kp_response::failure_reason::FailureReason;
+    use crate::api::kp::kp_response::KpResponse;
+    use crate::api::kp::refresh::request::send_refresh_request;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn retry() -> Retry {
+        Retry {
+            enabled: true,
+            max_attempts: 4,
+            base_delay_sec: 10,
+            max_total_delay_sec: 600,
+        }
+    }
+
+    fn http_response(status: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )
+    }
+
+    fn mock_server(responses: Vec<Option<String>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0; 4096];
+                let _ = stream.read(&mut request);
+                match response {
+                    Some(response) => stream.write_all(response.as_bytes()).unwrap(),
+                    None => thread::sleep(Duration::from_secs(2)),
+                }
+            }
+        });
+        url
+    }
+
+    fn refresh(url: &str, timeout: Option<Duration>) -> KpResponse {
+        send_refresh_request(&"Player.1234".to_string(), url.to_string(), timeout, false)
+    }
+
+    fn api_error(kp_response: &KpResponse) -> Option<&ApiError> {
+        match kp_response {
+            KpResponse::Failure(FailureReason::Api(error), _) => Some(error),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn bad_gateway_is_retried_until_success() {
+        let url = mock_server(vec![
+            Some(http_response("502 Bad Gateway", "")),
+            Some(http_response("502 Bad Gateway", "")),
+            Some(http_response("200 OK", r#"{"status":"ok"}"#)),
+        ]);
+        let now = Utc::now();
+        let mut attempts = None;
+        let mut delays = vec![];
+        loop {
+            let kp_response = refresh(&url, None);
+            match api_error(&kp_response) {
+                Some(error) => {
+                    assert_eq!(*error, ApiError::HttpStatus(502));
+                    assert!(error.is_transient());
+                    delays.push(record_failure(&mut attempts, &retry(), now, 0.0).unwrap());
+                }
+                None => {
+                    assert!(matches!(kp_response, KpResponse::Success(_)));
+                    break;
+                }
+            }
+        }
+        assert_eq!(
+            delays,
+            vec![Duration::from_secs(5), Duration::from_secs(10)]
+        );
+    }
+
+    #[test]
+    fn timeout_is_transient() {
+        let url = mock_server(vec![None]);
+        let kp_response = refresh(&url, Some(Duration::from_millis(200)));
+        let error = api_error(&kp_response).unwrap();
+        assert!(matches!(
+            error,
+            ApiError::Network {
+                kind: NetworkErrorKind::Timeout,
+                ..
+            }
+        ));
+        assert!(error.is_transient());
+        assert_eq!(kp_response.meta().unwrap().status, None);
+    }
+
+    #[test]
+    fn maintenance_page_is_transient() {
+        let url = mock_server(vec![Some(http_response(
+            "200 OK",
+            "<html>Down for maintenance</html>",
+        ))]);
+        let kp_response = refresh(&url, None);
+        assert_eq!(api_error(&kp_response), Some(&ApiError::Maintenance));
+        assert_eq!(kp_response.meta().unwrap().status, Some(200));
+    }
+
+    #[test]
+    fn not_found_is_not_retried() {
+        let url = mock_server(vec![Some(http_response("404 Not Found", ""))]);
+        let kp_response = refresh(&url, None);
+        assert!(matches!(
+            kp_response,
+            KpResponse::Failure(FailureReason::NotFound, _)
+        ));
+    }
+
+    #[test]
+    fn gives_up_after_max_attempts() {
+        let now = Utc::now();
+        let mut attempts = None;
+        for _ in 1..retry().max_attempts {
+            assert!(record_failure(&mut attempts, &retry(), now, 0.5).is_some());
+        }
+        assert_eq!(record_failure(&mut attempts, &retry(), now, 0.5), None);
+        assert_eq!(attempts, None);
+    }
+
+    #[test]
+    fn gives_up_when_total_delay_is_exceeded() {
+        let now = Utc::now();
+        let mut attempts = Some(RetryAttempts {
+            failed: 1,
+            first_failure_date: now - TimeDelta::seconds(595),
+        });
+        assert_eq!(record_failure(&mut attempts, &retry(), now, 0.0), None);
+    }
+
+    #[test]
+    fn jitter_stays_within_upper_half() {
+        for failed in 1..=3 {
+            let full = Duration::from_secs(10 * 2u64.pow(failed - 1));
+            assert_eq!(backoff_delay(10, failed, 0.0), full / 2);
+            assert!(backoff_delay(10, failed, 0.999) < full);
+        }
+        for _ in 0..100 {
+            assert!((0.0..1.0).contains(&jitter()));
+        }
+    }
+}
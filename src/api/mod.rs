@@ -1,7 +1,12 @@
+use crate::config::network::{valid_proxy, Network};
 use log::error;
-use reqwest::Error;
+use reqwest::blocking::{Client, Response};
+use reqwest::{Error, Proxy};
 use serde::de::StdError;
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::Duration;
 
+pub mod error;
 pub mod gw2;
 pub mod kp;
 
@@ -14,11 +19,71 @@ fn print_error_chain(error: &dyn StdError) {
     }
 }
 
-fn get_sync(url: String) -> Result<reqwest::blocking::Response, Error> {
-    let client = reqwest::blocking::Client::builder()
-        .danger_accept_invalid_hostnames(true)
+fn user_agent() -> String {
+    format!(
+        "{}/{} ({})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        env!("CARGO_PKG_REPOSITORY")
+    )
+}
+
+struct SharedClient {
+    network: Network,
+    client: Option<Client>,
+}
+
+fn shared_client() -> MutexGuard<'static, SharedClient> {
+    static SHARED_CLIENT: OnceLock<Mutex<SharedClient>> = OnceLock::new();
+    SHARED_CLIENT
+        .get_or_init(|| {
+            Mutex::new(SharedClient {
+                network: Network::default(),
+                client: None,
+            })
+        })
+        .lock()
+        .unwrap()
+}
+
+pub fn apply_network_settings(network: &Network) {
+    let mut shared = shared_client();
+    if shared.network != *network {
+        shared.network = network.clone();
+        shared.client = None;
+    }
+}
+
+fn client() -> Result<Client, Error> {
+    let mut shared = shared_client();
+    if let Some(client) = &shared.client {
+        return Ok(client.clone());
+    }
+    let client = build_client(&shared.network)?;
+    shared.client = Some(client.clone());
+    Ok(client)
+}
+
+fn build_client(network: &Network) -> Result<Client, Error> {
+    let mut builder = Client::builder()
         .use_rustls_tls()
-        .build()
-        .expect("error");
-    client.get(url).send()
+        .user_agent(user_agent())
+        .gzip(true)
+        .tcp_keepalive(Duration::from_secs(60))
+        .pool_idle_timeout(Duration::from_secs(90))
+        .connect_timeout(Duration::from_secs(network.connect_timeout_sec as u64))
+        .timeout(Duration::from_secs(network.read_timeout_sec as u64))
+        .danger_accept_invalid_hostnames(!network.strict_tls);
+    if valid_proxy(&network.proxy) {
+        builder = builder.proxy(Proxy::all(&network.proxy)?);
+    }
+    builder.build()
+}
+
+fn get_sync(url: String) -> Result<Response, Error> {
+    client()?.get(url).send()
+}
+
+fn get_sync_with_timeout(url: String, timeout: Duration) -> Result<Response, Error> {
+    client()?.get(url).timeout(timeout).send()
 }
@@ -0,0 +1,51 @@
+const ENCOUNTERS: &[(u64, &str)] = &[
+    // raids
+    (15438, "Vale Guardian"),
+    (15429, "Gorseval"),
+    (15375, "Sabetha"),
+    (16123, "Slothasor"),
+    (16088, "Bandit Trio"),
+    (16115, "Matthias"),
+    (16235, "Keep Construct"),
+    (16246, "Xera"),
+    (17194, "Cairn"),
+    (17172, "Mursaat Overseer"),
+    (17188, "Samarog"),
+    (17154, "Deimos"),
+    (19767, "Soulless Horror"),
+    (19691, "Broken King"),
+    (19536, "Eater of Souls"),
+    (19651, "Eye of Judgement"),
+    (19844, "Eye of Fate"),
+    (19450, "Dhuum"),
+    (43974, "Conjured Amalgamate"),
+    (21105, "Twin Largos"),
+    (21089, "Twin Largos"),
+    (20934, "Qadim"),
+    (22006, "Cardinal Adina"),
+    (21964, "Cardinal Sabir"),
+    (22000, "Qadim the Peerless"),
+    (26725, "Greer"),
+    (26774, "Decima"),
+    (26712, "Ura"),
+    // strikes
+    (22154, "Icebrood Construct"),
+    (22492, "Fraenir of Jormag"),
+    (22521, "Boneskinner"),
+    (22711, "Whisper of Jormag"),
+    (22836, "Cold War"),
+    (24033, "Aetherblade Hideout"),
+    (23957, "Xunlai Jade Junkyard"),
+    (24485, "Kaineng Overlook"),
+    (43488, "Harvest Temple"),
+    (25413, "Old Lion's Court"),
+    (25705, "Cosmic Observatory"),
+    (25989, "Temple of Febe"),
+];
+
+pub fn encounter_name(species_id: u64) -> Option<&'static str> {
+    ENCOUNTERS
+        .iter()
+        .find(|(id, _)| *id == species_id)
+        .map(|(_, name)| *name)
+}
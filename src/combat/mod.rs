@@ -0,0 +1,246 @@
+pub mod encounters;
+
+use crate::addon::Addon;
+use crate::combat::encounters::encounter_name;
+use function_name::named;
+use log::{debug, info};
+use std::ffi::c_char;
+use std::sync::MutexGuard;
+
+const STATECHANGE_ENTER_COMBAT: u8 = 1;
+const STATECHANGE_EXIT_COMBAT: u8 = 2;
+const STATECHANGE_LOG_START: u8 = 9;
+const STATECHANGE_LOG_END: u8 = 10;
+const STATECHANGE_REWARD: u8 = 19;
+const REWARD_GRACE_PERIOD_MS: u64 = 10_000;
+
+#[repr(C)]
+pub struct CombatData {
+    pub event: *const CombatEvent,
+    pub src: *const Agent,
+    pub dst: *const Agent,
+    pub skill_name: *const c_char,
+    pub id: u64,
+    pub revision: u64,
+}
+
+#[repr(C)]
+pub struct CombatEvent {
+    pub time: u64,
+    pub src_agent: u64,
+    pub dst_agent: u64,
+    pub value: i32,
+    pub buff_dmg: i32,
+    pub overstack_value: u32,
+    pub skill_id: u32,
+    pub src_instance_id: u16,
+    pub dst_instance_id: u16,
+    pub src_master_instance_id: u16,
+    pub dst_master_instance_id: u16,
+    pub iff: u8,
+    pub buff: u8,
+    pub result: u8,
+    pub is_activation: u8,
+    pub is_buff_remove: u8,
+    pub is_ninety: u8,
+    pub is_fifty: u8,
+    pub is_moving: u8,
+    pub is_statechange: u8,
+    pub is_flanking: u8,
+    pub is_shields: u8,
+    pub is_off_cycle: u8,
+    pub pad61: u8,
+    pub pad62: u8,
+    pub pad63: u8,
+    pub pad64: u8,
+}
+
+#[repr(C)]
+pub struct Agent {
+    pub name: *const c_char,
+    pub id: usize,
+    pub prof: u32,
+    pub elite: u32,
+    pub is_self: u32,
+    pub team: u16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EncounterTracker {
+    current: Option<u64>,
+    last_ended: Option<(u64, u64)>,
+}
+
+impl EncounterTracker {
+    // The reward may arrive shortly after the log ended, so the last ended encounter is kept.
+    pub fn handle(&mut self, data: &CombatData) -> Option<u64> {
+        let event = unsafe { data.event.as_ref() }?;
+        match event.is_statechange {
+            STATECHANGE_LOG_START => {
+                self.current = encounter_name(event.src_agent).map(|_| event.src_agent);
+                self.last_ended = None;
+                None
+            }
+            STATECHANGE_LOG_END => {
+                self.last_ended = self
+                    .current
+                    .take()
+                    .map(|species_id| (species_id, event.time));
+                None
+            }
+            STATECHANGE_REWARD => self.current.take().or_else(|| {
+                let (species_id, ended) = self.last_ended.take()?;
+                (event.time.saturating_sub(ended) <= REWARD_GRACE_PERIOD_MS).then_some(species_id)
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[named]
+pub fn handle_combat_event(data: &CombatData) {
+    if data.event.is_null() {
+        return;
+    }
+    let event = unsafe { &*data.event };
+    match event.is_statechange {
+        STATECHANGE_ENTER_COMBAT | STATECHANGE_EXIT_COMBAT => {
+            let src_is_self = unsafe { data.src.as_ref() }.is_some_and(|src| src.is_self != 0);
+            if src_is_self {
+                Addon::lock().context.in_combat = event.is_statechange == STATECHANGE_ENTER_COMBAT;
+            }
+        }
+        STATECHANGE_LOG_START | STATECHANGE_LOG_END | STATECHANGE_REWARD => {
+            let mut addon = Addon::lock();
+            if event.is_statechange == STATECHANGE_LOG_START {
+                debug!(
+                    "[{}] log started for species {}",
+                    function_name!(),
+                    event.src_agent
+                );
+            }
+            if let Some(species_id) = addon.context.encounter.handle(data) {
+                record_kill(&mut addon, species_id);
+            }
+        }
+        _ => {}
+    }
+}
+
+// kills only mark KP as unrefreshed, the refresh itself is left to the scheduling rules
+#[named]
+pub fn record_kill(addon: &mut MutexGuard<Addon>, species_id: u64) {
+    if let Some(name) = encounter_name(species_id) {
+        info!("[{}] detected kill: {}", function_name!(), name);
+        addon.context.record_kill(species_id, name);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    const VALE_GUARDIAN: u64 = 15438;
+
+    fn event(statechange: u8, src_agent: u64, time: u64) -> CombatEvent {
+        CombatEvent {
+            time,
+            src_agent,
+            dst_agent: 0,
+            value: 0,
+            buff_dmg: 0,
+            overstack_value: 0,
+            skill_id: 0,
+            src_instance_id: 0,
+            dst_instance_id: 0,
+            src_master_instance_id: 0,
+            dst_master_instance_id: 0,
+            iff: 0,
+            buff: 0,
+            result: 0,
+            is_activation: 0,
+            is_buff_remove: 0,
+            is_ninety: 0,
+            is_fifty: 0,
+            is_moving: 0,
+            is_statechange: statechange,
+            is_flanking: 0,
+            is_shields: 0,
+            is_off_cycle: 0,
+            pad61: 0,
+            pad62: 0,
+            pad63: 0,
+            pad64: 0,
+        }
+    }
+
+    fn combat_data(event: &CombatEvent) -> CombatData {
+        CombatData {
+            event,
+            src: ptr::null(),
+            dst: ptr::null(),
+            skill_name: ptr::null(),
+            id: 0,
+            revision: 1,
+        }
+    }
+
+    fn kills(events: &[CombatEvent]) -> Vec<u64> {
+        let mut tracker = EncounterTracker::default();
+        events
+            .iter()
+            .filter_map(|event| tracker.handle(&combat_data(event)))
+            .collect()
+    }
+
+    #[test]
+    fn reward_before_log_end_counts_kill() {
+        let events = [
+            event(STATECHANGE_LOG_START, VALE_GUARDIAN, 0),
+            event(STATECHANGE_REWARD, 0, 1_000),
+            event(STATECHANGE_LOG_END, VALE_GUARDIAN, 2_000),
+        ];
+        assert_eq!(kills(&events), vec![VALE_GUARDIAN]);
+    }
+
+    #[test]
+    fn reward_after_log_end_counts_kill() {
+        let events = [
+            event(STATECHANGE_LOG_START, VALE_GUARDIAN, 0),
+            event(STATECHANGE_LOG_END, VALE_GUARDIAN, 1_000),
+            event(STATECHANGE_REWARD, 0, 3_000),
+        ];
+        assert_eq!(kills(&events), vec![VALE_GUARDIAN]);
+    }
+
+    #[test]
+    fn late_reward_is_ignored() {
+        let events = [
+            event(STATECHANGE_LOG_START, VALE_GUARDIAN, 0),
+            event(STATECHANGE_LOG_END, VALE_GUARDIAN, 1_000),
+            event(STATECHANGE_REWARD, 0, 1_000 + REWARD_GRACE_PERIOD_MS + 1),
+        ];
+        assert!(kills(&events).is_empty());
+    }
+
+    #[test]
+    fn reward_is_counted_once() {
+        let events = [
+            event(STATECHANGE_LOG_START, VALE_GUARDIAN, 0),
+            event(STATECHANGE_REWARD, 0, 1_000),
+            event(STATECHANGE_LOG_END, VALE_GUARDIAN, 2_000),
+            event(STATECHANGE_REWARD, 0, 3_000),
+        ];
+        assert_eq!(kills(&events), vec![VALE_GUARDIAN]);
+    }
+
+    #[test]
+    fn unknown_species_is_ignored() {
+        let events = [
+            event(STATECHANGE_LOG_START, 1, 0),
+            event(STATECHANGE_REWARD, 0, 1_000),
+        ];
+        assert!(kills(&events).is_empty());
+    }
+}
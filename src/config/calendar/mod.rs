@@ -0,0 +1,40 @@
+use chrono::{NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Calendar {
+    pub enabled: bool,
+    pub refresh_minutes_before: u32,
+    pub refresh_minutes_after: u32,
+    pub events: Vec<CalendarEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CalendarEvent {
+    pub name: String,
+    pub days: Vec<Weekday>,
+    pub start_time: NaiveTime,
+    pub duration_minutes: u32,
+}
+
+impl Calendar {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_minutes_before: 5,
+            refresh_minutes_after: 5,
+            events: vec![],
+        }
+    }
+}
+
+impl CalendarEvent {
+    pub fn default() -> Self {
+        Self {
+            name: "Raid night".to_string(),
+            days: vec![Weekday::Tue, Weekday::Thu],
+            start_time: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+            duration_minutes: 180,
+        }
+    }
+}
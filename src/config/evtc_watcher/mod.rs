@@ -0,0 +1,33 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EvtcWatcher {
+    pub enabled: bool,
+    pub directory: String,
+}
+
+impl EvtcWatcher {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_directory(),
+        }
+    }
+}
+
+fn default_directory() -> String {
+    env::var("USERPROFILE")
+        .map(|home| {
+            PathBuf::from(home)
+                .join("Documents")
+                .join("Guild Wars 2")
+                .join("addons")
+                .join("arcdps")
+                .join("arcdps.cbtlogs")
+                .display()
+                .to_string()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FolderSync {
+    pub enabled: bool,
+    pub directory: String,
+}
+
+impl FolderSync {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: "".to_string(),
+        }
+    }
+}
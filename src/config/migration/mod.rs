@@ -0,0 +1,214 @@
+use crate::addon::VERSION;
+use log::warn;
+use semver::Version;
+use serde_json::Value;
+
+pub struct Migration {
+    pub id: &'static str,
+    pub version: &'static str,
+    pub description: &'static str,
+    pub apply: fn(&mut Value) -> bool,
+}
+
+// every migration must be idempotent
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        id: "retain_map_1154",
+        version: "0.9.6",
+        description: "add map 1154 to refresh retaining maps",
+        apply: |config| add_map_id(config, "retain_refresh_map_ids", 1154),
+    },
+    Migration {
+        id: "kp_map_1564",
+        version: "1.0.0",
+        description: "add map 1564 to KP maps",
+        apply: |config| add_map_id(config, "kp_map_ids", 1564),
+    },
+    Migration {
+        id: "kp_map_1609",
+        version: "1.3.1",
+        description: "add map 1609 to KP maps",
+        apply: |config| add_map_id(config, "kp_map_ids", 1609),
+    },
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MigrationReport {
+    pub from_version: Option<String>,
+    pub applied: Vec<&'static str>,
+}
+
+impl MigrationReport {
+    pub fn changed(&self) -> bool {
+        !self.applied.is_empty()
+    }
+}
+
+pub fn migrate(config: &mut Value, dry_run: bool) -> MigrationReport {
+    migrate_with(config, MIGRATIONS, dry_run)
+}
+
+pub fn migrate_with(value: &mut Value, migrations: &[Migration], dry_run: bool) -> MigrationReport {
+    let from_version = value
+        .get("version")
+        .and_then(|version| version.as_str())
+        .map(|version| version.to_string());
+    let parsed_version = from_version
+        .as_deref()
+        .and_then(|version| Version::parse(version).ok());
+    match &parsed_version {
+        None => warn!(
+            "Version {:?} is missing or malformed, applying all migrations",
+            from_version
+        ),
+        Some(version) if *version > Version::parse(VERSION).expect("invalid addon version") => {
+            warn!(
+                "Saved by newer version {}, unknown fields may be dropped",
+                version
+            )
+        }
+        Some(_) => {}
+    }
+
+    let mut working_copy = value.clone();
+    let mut applied = vec![];
+    for migration in migrations {
+        let target = Version::parse(migration.version).expect("invalid migration version");
+        if parsed_version
+            .as_ref()
+            .is_some_and(|version| *version >= target)
+        {
+            continue;
+        }
+        if (migration.apply)(&mut working_copy) {
+            applied.push(migration.id);
+        }
+    }
+    if !dry_run {
+        if let Some(object) = working_copy.as_object_mut() {
+            object.insert("version".to_string(), Value::from(VERSION));
+        }
+        *value = working_copy;
+    }
+    MigrationReport {
+        from_version,
+        applied,
+    }
+}
+
+pub fn migration_description(id: &str) -> Option<&'static str> {
+    MIGRATIONS
+        .iter()
+        .find(|migration| migration.id == id)
+        .map(|migration| migration.description)
+}
+
+fn add_map_id(config: &mut Value, key: &str, map_id: u32) -> bool {
+    let Some(map_ids) = config.get_mut(key).and_then(|ids| ids.as_array_mut()) else {
+        return false;
+    };
+    if map_ids.iter().any(|id| id.as_u64() == Some(map_id as u64)) {
+        return false;
+    }
+    map_ids.push(Value::from(map_id));
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use std::fs;
+
+    fn fixture(name: &str) -> Value {
+        let path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/config/migration/fixtures")
+            .join(name);
+        serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+    }
+
+    fn migrated_config(name: &str) -> (Config, MigrationReport) {
+        let mut value = fixture(name);
+        let report = migrate(&mut value, false);
+        (serde_json::from_value(value).unwrap(), report)
+    }
+
+    #[test]
+    fn migrates_0_9_5() {
+        let (config, report) = migrated_config("config_0.9.5.json");
+        assert_eq!(
+            report.applied,
+            vec!["retain_map_1154", "kp_map_1564", "kp_map_1609"]
+        );
+        assert!(config.retain_refresh_map_ids.contains(&1154));
+        assert!(config.kp_map_ids.contains(&1564));
+        assert!(config.kp_map_ids.contains(&1609));
+        assert!(config.autodetect_account_name);
+        assert_eq!(config.version, VERSION);
+    }
+
+    #[test]
+    fn migrates_1_0_0() {
+        let (config, report) = migrated_config("config_1.0.0.json");
+        assert_eq!(report.applied, vec!["kp_map_1609"]);
+        assert!(config.kp_map_ids.contains(&1609));
+    }
+
+    #[test]
+    fn migrates_1_3_0() {
+        let (config, report) = migrated_config("config_1.3.0.json");
+        assert_eq!(report.applied, vec!["kp_map_1609"]);
+        assert_eq!(config.kp_identifiers.main_id, "Player.1234");
+    }
+
+    #[test]
+    fn leaves_current_config_unchanged() {
+        let (_, report) = migrated_config("config_1.3.1.json");
+        assert!(!report.changed());
+    }
+
+    #[test]
+    fn keeps_user_removed_maps_removed() {
+        let mut value = fixture("config_1.3.1.json");
+        value["kp_map_ids"] = Value::from(vec![1339]);
+        let report = migrate(&mut value, false);
+        assert!(!report.changed());
+        assert_eq!(value["kp_map_ids"], Value::from(vec![1339]));
+    }
+
+    #[test]
+    fn handles_malformed_version() {
+        let (config, report) = migrated_config("config_malformed_version.json");
+        assert_eq!(report.from_version.as_deref(), Some("1.2"));
+        assert_eq!(report.applied, vec!["kp_map_1609"]);
+        assert!(config.kp_map_ids.contains(&1609));
+        assert_eq!(config.version, VERSION);
+    }
+
+    #[test]
+    fn handles_missing_version() {
+        let mut value = fixture("config_0.9.5.json");
+        value.as_object_mut().unwrap().remove("version");
+        let report = migrate(&mut value, false);
+        assert_eq!(report.from_version, None);
+        assert_eq!(value["version"], Value::from(VERSION));
+    }
+
+    #[test]
+    fn dry_run_does_not_modify() {
+        let original = fixture("config_0.9.5.json");
+        let mut value = original.clone();
+        let report = migrate(&mut value, true);
+        assert_eq!(report.applied.len(), 3);
+        assert_eq!(value, original);
+    }
+
+    #[test]
+    fn migration_versions_are_ordered() {
+        let versions: Vec<Version> = MIGRATIONS
+            .iter()
+            .map(|migration| Version::parse(migration.version).unwrap())
+            .collect();
+        assert!(versions.windows(2).all(|pair| pair[0] <= pair[1]));
+    }
+}
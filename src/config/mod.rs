@@ -1,19 +1,42 @@
+pub mod calendar;
+mod evtc_watcher;
+mod folder_sync;
 mod killproof_identifiers;
+pub mod migration;
+pub mod network;
 mod notifications;
+pub mod presets;
+pub mod reload;
+pub mod retry;
+pub mod rules;
+pub mod share;
+mod squad_triggers;
+mod unload_refresh;
+pub mod validation;
+mod weekly_reset;
 
-use crate::addon::{Addon, VERSION};
+use crate::addon::VERSION;
+use crate::config::calendar::Calendar;
+use crate::config::evtc_watcher::EvtcWatcher;
+use crate::config::folder_sync::FolderSync;
 use crate::config::killproof_identifiers::KillproofIdentifiers;
+use crate::config::migration::{migrate, migration_description};
+use crate::config::network::Network;
 use crate::config::notifications::Notifications;
-use chrono::{DateTime, Local};
+use crate::config::presets::{default_presets, Preset};
+use crate::config::retry::Retry;
+use crate::config::rules::{default_rules, Rule};
+use crate::config::squad_triggers::SquadTriggers;
+use crate::config::unload_refresh::UnloadRefresh;
+use crate::config::weekly_reset::WeeklyReset;
+use crate::storage::{load_json_with, save_json, take_loaded, BACKUP_COUNT};
 use log::info;
 use nexus::paths::get_addon_dir;
 use regex::Regex;
-use semver::Version;
 use serde::{Deserialize, Serialize};
-use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::fs;
 use std::path::PathBuf;
-use std::sync::MutexGuard;
+use std::time::SystemTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Config {
@@ -21,18 +44,42 @@ pub struct Config {
     pub version: String,
     #[serde(default = "KillproofIdentifiers::default")]
     pub kp_identifiers: KillproofIdentifiers,
-    pub last_refresh_date: Option<DateTime<Local>>,
-    pub refresh_on_next_load: bool,
     #[serde(default = "default_kp_map_ids")]
     pub kp_map_ids: Vec<u32>,
     #[serde(default = "default_retain_refresh_map_ids")]
     pub retain_refresh_map_ids: Vec<u32>,
     #[serde(default = "Notifications::default")]
     pub notifications: Notifications,
-    #[serde(default = "yes")]
-    pub scheduling_on_map_enter_enabled: bool,
+    #[serde(default = "yes", alias = "scheduling_on_map_enter_enabled")]
+    pub scheduling_rules_enabled: bool,
     #[serde(alias = "use_arcdps")]
     pub autodetect_account_name: bool,
+    #[serde(default = "SquadTriggers::default")]
+    pub squad_triggers: SquadTriggers,
+    #[serde(default)]
+    pub refresh_only_on_kill: bool,
+    #[serde(default = "EvtcWatcher::default")]
+    pub evtc_watcher: EvtcWatcher,
+    #[serde(default = "WeeklyReset::default")]
+    pub weekly_reset: WeeklyReset,
+    #[serde(default = "Calendar::default")]
+    pub calendar: Calendar,
+    #[serde(default = "default_rules")]
+    pub rules: Vec<Rule>,
+    #[serde(default)]
+    pub scheduling_disabled_characters: Vec<String>,
+    #[serde(default = "UnloadRefresh::default")]
+    pub unload_refresh: UnloadRefresh,
+    #[serde(default = "default_date_format")]
+    pub date_format: String,
+    #[serde(default = "default_presets")]
+    pub presets: Vec<Preset>,
+    #[serde(default = "FolderSync::default")]
+    pub folder_sync: FolderSync,
+    #[serde(default = "Network::default")]
+    pub network: Network,
+    #[serde(default = "Retry::default")]
+    pub retry: Retry,
 }
 
 const REGEX_KP_ID: &str = r"^([a-zA-Z0-9]{3,17}|[a-zA-Z0-9 ]+\.[0-9]{4})$";
@@ -42,39 +89,47 @@ impl Default for Config {
         Self {
             version: VERSION.to_string(),
             kp_identifiers: KillproofIdentifiers::default(),
-            last_refresh_date: None,
-            refresh_on_next_load: false,
             kp_map_ids: default_kp_map_ids(),
             retain_refresh_map_ids: default_retain_refresh_map_ids(),
             notifications: Notifications::default(),
-            scheduling_on_map_enter_enabled: yes(),
+            scheduling_rules_enabled: yes(),
             autodetect_account_name: false,
+            squad_triggers: SquadTriggers::default(),
+            refresh_only_on_kill: false,
+            evtc_watcher: EvtcWatcher::default(),
+            weekly_reset: WeeklyReset::default(),
+            calendar: Calendar::default(),
+            rules: default_rules(),
+            scheduling_disabled_characters: vec![],
+            unload_refresh: UnloadRefresh::default(),
+            date_format: default_date_format(),
+            presets: default_presets(),
+            folder_sync: FolderSync::default(),
+            network: Network::default(),
+            retry: Retry::default(),
         }
     }
 }
 
 impl Config {
     pub fn try_load() -> Option<Self> {
-        let path = Self::file();
-        let file = File::open(&path)
-            .inspect_err(|err| log::warn!("Failed to read config: {err}"))
-            .ok()?;
-        let reader = BufReader::new(file);
-        let config = serde_json::from_reader(reader)
-            .inspect_err(|err| log::warn!("Failed to parse config: {err}"))
-            .ok()?;
-        info!("Loaded config from \"{}\"", path.display());
-        Some(config)
+        let result = load_json_with(&Self::file(), BACKUP_COUNT, |mut value| {
+            let report = migrate(&mut value, false);
+            for id in &report.applied {
+                info!(
+                    "Config migrated from {:?}: {}",
+                    report.from_version,
+                    migration_description(id).unwrap_or(id)
+                );
+            }
+            serde_json::from_value(value).map_err(|err| err.to_string())
+        });
+        take_loaded(result, "Configuration")
     }
 
     pub fn save(&self) {
-        let path = Self::file();
-        match File::create(&path) {
-            Ok(file) => {
-                let writer = BufWriter::new(file);
-                serde_json::to_writer_pretty(writer, &self).expect("failed to serialize config");
-            }
-            Err(err) => log::error!("Failed to save config: {err}"),
+        if let Err(err) = save_json(&Self::file(), self, BACKUP_COUNT) {
+            log::error!("Failed to save config: {err}");
         }
     }
 
@@ -82,16 +137,30 @@ impl Config {
         config_dir().join("config.json")
     }
 
+    pub fn file_modified() -> Option<SystemTime> {
+        fs::metadata(Self::file())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    }
+
     pub fn valid(&self) -> bool {
-        let re = Regex::new(REGEX_KP_ID).unwrap();
-        re.is_match(self.kp_identifiers.main_id.as_str())
+        valid_kp_id(self.kp_identifiers.main_id.as_str())
     }
 }
 
+pub fn valid_kp_id(kp_id: &str) -> bool {
+    let re = Regex::new(REGEX_KP_ID).unwrap();
+    re.is_match(kp_id)
+}
+
 pub fn config_dir() -> PathBuf {
     get_addon_dir("kp_sync").expect("invalid config directory")
 }
 
+pub fn default_date_format() -> String {
+    "%Y-%m-%d %H:%M".to_string()
+}
+
 fn default_version() -> String {
     VERSION.to_string()
 }
@@ -109,29 +178,6 @@ fn default_retain_refresh_map_ids() -> Vec<u32> {
     ]
 }
 
-pub fn migrate_configs(addon: &mut MutexGuard<Addon>) {
-    if version_older_than(addon.config.version.as_str(), "0.9.6")
-        && !addon.config.retain_refresh_map_ids.contains(&1154)
-    {
-        addon.config.retain_refresh_map_ids.push(1154);
-    }
-    if version_older_than(addon.config.version.as_str(), "1.0.0")
-        && !addon.config.kp_map_ids.contains(&1564)
-    {
-        addon.config.kp_map_ids.push(1564);
-    }
-    if version_older_than(addon.config.version.as_str(), "1.3.1")
-        && !addon.config.kp_map_ids.contains(&1609)
-    {
-        addon.config.kp_map_ids.push(1609);
-    }
-    addon.config.version = VERSION.to_string();
-}
-
-fn version_older_than(older: &str, than: &str) -> bool {
-    Version::parse(older).unwrap() < Version::parse(than).unwrap()
-}
-
 fn yes() -> bool {
     true
 }
@@ -0,0 +1,28 @@
+use reqwest::Proxy;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Network {
+    pub connect_timeout_sec: u32,
+    pub read_timeout_sec: u32,
+    pub proxy: String,
+    pub strict_tls: bool,
+}
+
+impl Network {
+    pub fn default() -> Self {
+        Self {
+            connect_timeout_sec: 10,
+            read_timeout_sec: 30,
+            proxy: "".to_string(),
+            strict_tls: true,
+        }
+    }
+}
+
+pub fn valid_proxy(proxy: &str) -> bool {
+    let scheme_supported = ["http://", "https://", "socks5://", "socks5h://"]
+        .iter()
+        .any(|scheme| proxy.starts_with(scheme));
+    scheme_supported && Proxy::all(proxy).is_ok()
+}
@@ -6,6 +6,8 @@ pub struct Notifications {
     pub notify_retry: bool,
     pub notify_failure: bool,
     pub notify_failure_linked: bool,
+    #[serde(default = "yes")]
+    pub notify_session_summary: bool,
 }
 
 impl Notifications {
@@ -15,6 +17,11 @@ impl Notifications {
             notify_retry: true,
             notify_failure: false,
             notify_failure_linked: false,
+            notify_session_summary: true,
         }
     }
 }
+
+fn yes() -> bool {
+    true
+}
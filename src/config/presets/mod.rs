@@ -0,0 +1,152 @@
+use crate::addon::Addon;
+use crate::config::notifications::Notifications;
+use crate::config::Config;
+use ::function_name::named;
+use log::info;
+use nexus::alert::send_alert;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub kp_map_ids: Option<Vec<u32>>,
+    #[serde(default)]
+    pub retain_refresh_map_ids: Option<Vec<u32>>,
+    #[serde(default)]
+    pub notifications: Option<Notifications>,
+    #[serde(default, alias = "scheduling_on_map_enter_enabled")]
+    pub scheduling_rules_enabled: Option<bool>,
+    #[serde(default)]
+    pub refresh_only_on_kill: Option<bool>,
+}
+
+impl Preset {
+    pub fn capture(name: &str, config: &Config) -> Self {
+        Self {
+            name: name.to_string(),
+            kp_map_ids: Some(config.kp_map_ids.clone()),
+            retain_refresh_map_ids: Some(config.retain_refresh_map_ids.clone()),
+            notifications: Some(config.notifications.clone()),
+            scheduling_rules_enabled: Some(config.scheduling_rules_enabled),
+            refresh_only_on_kill: Some(config.refresh_only_on_kill),
+        }
+    }
+
+    pub fn update_from(&mut self, current: &Preset) {
+        if self.kp_map_ids.is_some() {
+            self.kp_map_ids = current.kp_map_ids.clone();
+        }
+        if self.retain_refresh_map_ids.is_some() {
+            self.retain_refresh_map_ids = current.retain_refresh_map_ids.clone();
+        }
+        if self.notifications.is_some() {
+            self.notifications = current.notifications.clone();
+        }
+        if self.scheduling_rules_enabled.is_some() {
+            self.scheduling_rules_enabled = current.scheduling_rules_enabled;
+        }
+        if self.refresh_only_on_kill.is_some() {
+            self.refresh_only_on_kill = current.refresh_only_on_kill;
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config) {
+        if let Some(kp_map_ids) = &self.kp_map_ids {
+            config.kp_map_ids = kp_map_ids.clone();
+        }
+        if let Some(retain_refresh_map_ids) = &self.retain_refresh_map_ids {
+            config.retain_refresh_map_ids = retain_refresh_map_ids.clone();
+        }
+        if let Some(notifications) = &self.notifications {
+            config.notifications = notifications.clone();
+        }
+        if let Some(enabled) = self.scheduling_rules_enabled {
+            config.scheduling_rules_enabled = enabled;
+        }
+        if let Some(refresh_only_on_kill) = self.refresh_only_on_kill {
+            config.refresh_only_on_kill = refresh_only_on_kill;
+        }
+    }
+}
+
+pub fn default_presets() -> Vec<Preset> {
+    vec![
+        Preset {
+            name: "Raid night".to_string(),
+            kp_map_ids: None,
+            retain_refresh_map_ids: None,
+            notifications: Some(Notifications {
+                notify_success: true,
+                notify_retry: true,
+                notify_failure: true,
+                notify_failure_linked: true,
+                notify_session_summary: true,
+            }),
+            scheduling_rules_enabled: Some(true),
+            refresh_only_on_kill: Some(false),
+        },
+        Preset {
+            name: "Casual".to_string(),
+            kp_map_ids: None,
+            retain_refresh_map_ids: None,
+            notifications: Some(Notifications {
+                notify_success: false,
+                notify_retry: false,
+                notify_failure: true,
+                notify_failure_linked: true,
+                notify_session_summary: false,
+            }),
+            scheduling_rules_enabled: Some(false),
+            refresh_only_on_kill: None,
+        },
+    ]
+}
+
+impl Addon {
+    pub fn active_preset_index(&self) -> Option<usize> {
+        let active = self.state.active_preset.as_ref()?;
+        self.config
+            .presets
+            .iter()
+            .position(|preset| &preset.name == active)
+    }
+
+    #[named]
+    pub fn activate_preset(&mut self, index: Option<usize>) {
+        if let Some(base) = self.state.preset_base.take() {
+            base.apply(&mut self.config);
+        }
+        self.state.active_preset = None;
+        if let Some(preset) = index.and_then(|i| self.config.presets.get(i)).cloned() {
+            info!(
+                "[{}] preset \"{}\" activated",
+                function_name!(),
+                preset.name
+            );
+            self.state.preset_base = Some(Preset::capture("", &self.config));
+            preset.apply(&mut self.config);
+            self.state.active_preset = Some(preset.name);
+        } else {
+            info!("[{}] preset deactivated", function_name!());
+        }
+        self.validate_config();
+    }
+
+    pub fn cycle_preset(&mut self) {
+        let next = match self.active_preset_index() {
+            None if !self.config.presets.is_empty() => Some(0),
+            Some(i) if i + 1 < self.config.presets.len() => Some(i + 1),
+            _ => None,
+        };
+        self.activate_preset(next);
+        send_alert(format!("Active preset: {}", self.active_preset_text()));
+    }
+
+    pub fn active_preset_text(&self) -> String {
+        self.state
+            .active_preset
+            .clone()
+            .unwrap_or_else(|| "none".to_string())
+    }
+}
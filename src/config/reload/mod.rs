@@ -0,0 +1,224 @@
+use crate::addon::Addon;
+use crate::api::apply_network_settings;
+use crate::config::migration::migrate;
+use crate::config::Config;
+use ::function_name::named;
+use log::{info, warn};
+use nexus::alert::send_alert;
+use serde_json::{Map, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::MutexGuard;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeSummary {
+    pub from_disk: Vec<String>,
+    pub kept_local: Vec<String>,
+}
+
+pub fn merge_configs(base: &Value, ours: &Value, theirs: &Value) -> (Value, MergeSummary) {
+    let empty = Map::new();
+    let base = base.as_object().unwrap_or(&empty);
+    let ours = ours.as_object().unwrap_or(&empty);
+    let theirs = theirs.as_object().unwrap_or(&empty);
+
+    let mut merged = theirs.clone();
+    let mut summary = MergeSummary::default();
+    for (key, their_value) in theirs {
+        let base_value = base.get(key);
+        let our_value = ours.get(key);
+        if base_value != Some(their_value) {
+            summary.from_disk.push(key.clone());
+        } else if let Some(our_value) = our_value.filter(|value| Some(*value) != base_value) {
+            merged.insert(key.clone(), our_value.clone());
+            summary.kept_local.push(key.clone());
+        }
+    }
+    summary.from_disk.retain(|key| key != "version");
+    (Value::Object(merged), summary)
+}
+
+pub struct ReloadedConfig {
+    pub config: Config,
+    pub disk_config: Config,
+    pub summary: MergeSummary,
+}
+
+#[named]
+pub fn reload_config(
+    path: &Path,
+    saved_config: Option<&Config>,
+    config: &Config,
+) -> Result<ReloadedConfig, String> {
+    let disk_config = read_config_file(path)?;
+    let base = saved_config
+        .and_then(|config| serde_json::to_value(config).ok())
+        .unwrap_or(Value::Null);
+    let ours = serde_json::to_value(config).unwrap_or(Value::Null);
+    let theirs = serde_json::to_value(&disk_config).unwrap_or(Value::Null);
+    let (merged, summary) = merge_configs(&base, &ours, &theirs);
+    let merged_config = match serde_json::from_value::<Config>(merged) {
+        Ok(config) => config,
+        Err(err) => {
+            warn!("[{}] merge failed, using file: {}", function_name!(), err);
+            disk_config.clone()
+        }
+    };
+    Ok(ReloadedConfig {
+        config: merged_config,
+        disk_config,
+        summary,
+    })
+}
+
+fn read_config_file(path: &Path) -> Result<Config, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let mut value: Value =
+        serde_json::from_reader(BufReader::new(file)).map_err(|err| err.to_string())?;
+    migrate(&mut value, false);
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
+
+#[named]
+pub fn reload_config_if_changed(addon: &mut MutexGuard<Addon>) -> bool {
+    let modified = Config::file_modified();
+    if modified.is_none() || modified == addon.context.config_file_modified {
+        return true;
+    }
+    if modified == addon.context.rejected_config_file_modified {
+        return false;
+    }
+
+    let reloaded = match reload_config(
+        &Config::file(),
+        addon.context.saved_config.as_ref(),
+        &addon.config,
+    ) {
+        Ok(reloaded) => reloaded,
+        Err(err) => {
+            warn!("[{}] edited config is invalid: {}", function_name!(), err);
+            addon.context.rejected_config_file_modified = modified;
+            send_alert(format!(
+                "Edited config.json could not be loaded, fix it to apply changes ({})",
+                err
+            ));
+            return false;
+        }
+    };
+    let summary = reloaded.summary;
+
+    info!(
+        "[{}] config reloaded, from disk: {:?}, kept: {:?}",
+        function_name!(),
+        summary.from_disk,
+        summary.kept_local
+    );
+    addon.config = reloaded.config;
+    addon.context.saved_config = Some(reloaded.disk_config);
+    addon.context.config_file_modified = modified;
+    addon.context.rejected_config_file_modified = None;
+    if !summary.from_disk.is_empty() {
+        let mut text = format!(
+            "Config reloaded from disk, changed: {}",
+            summary.from_disk.join(", ")
+        );
+        if !summary.kept_local.is_empty() {
+            text.push_str(&format!(
+                " (kept in-game changes: {})",
+                summary.kept_local.join(", ")
+            ));
+        }
+        send_alert(text);
+    }
+    apply_network_settings(&addon.config.network);
+    addon.validate_config();
+    addon.report_config_issues();
+    true
+}
+
+pub fn save_config_if_changed(addon: &mut MutexGuard<Addon>) -> bool {
+    if !reload_config_if_changed(addon)
+        || addon.context.saved_config.as_ref() == Some(&addon.config)
+    {
+        return false;
+    }
+    addon.config.save();
+    addon.context.saved_config = Some(addon.config.clone());
+    addon.context.config_file_modified = Config::file_modified();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+
+    fn write_config(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "kp_sync_reload_{}_{}.json",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn reload(name: &str, saved: &Config, ours: &Config, disk: &Config) -> ReloadedConfig {
+        let path = write_config(name, &serde_json::to_string(disk).unwrap());
+        let reloaded = reload_config(&path, Some(saved), ours).unwrap();
+        let _ = fs::remove_file(path);
+        reloaded
+    }
+
+    #[test]
+    fn applies_disk_only_change() {
+        let saved = Config::default();
+        let mut disk = saved.clone();
+        disk.date_format = "%d.%m.%Y".to_string();
+        let reloaded = reload("disk_only", &saved, &saved, &disk);
+        assert_eq!(reloaded.config.date_format, "%d.%m.%Y");
+        assert_eq!(reloaded.summary.from_disk, vec!["date_format"]);
+        assert!(reloaded.summary.kept_local.is_empty());
+    }
+
+    #[test]
+    fn keeps_memory_only_change() {
+        let saved = Config::default();
+        let mut ours = saved.clone();
+        ours.refresh_only_on_kill = !saved.refresh_only_on_kill;
+        let reloaded = reload("memory_only", &saved, &ours, &saved);
+        assert_eq!(reloaded.config, ours);
+        assert!(reloaded.summary.from_disk.is_empty());
+        assert_eq!(reloaded.summary.kept_local, vec!["refresh_only_on_kill"]);
+    }
+
+    #[test]
+    fn disk_wins_conflict() {
+        let saved = Config::default();
+        let mut ours = saved.clone();
+        ours.date_format = "%H:%M".to_string();
+        ours.refresh_only_on_kill = !saved.refresh_only_on_kill;
+        let mut disk = saved.clone();
+        disk.date_format = "%d.%m.%Y".to_string();
+        let reloaded = reload("conflict", &saved, &ours, &disk);
+        assert_eq!(reloaded.config.date_format, "%d.%m.%Y");
+        assert_eq!(
+            reloaded.config.refresh_only_on_kill,
+            ours.refresh_only_on_kill
+        );
+        assert_eq!(reloaded.summary.from_disk, vec!["date_format"]);
+        assert_eq!(reloaded.summary.kept_local, vec!["refresh_only_on_kill"]);
+    }
+
+    #[test]
+    fn invalid_json_is_rejected_and_left_untouched() {
+        let contents = r#"{ "kp_map_ids": [1339, "#;
+        let path = write_config("invalid", contents);
+        let config = Config::default();
+        assert!(reload_config(&path, Some(&config), &config).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        let _ = fs::remove_file(path);
+    }
+}
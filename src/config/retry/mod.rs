@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Retry {
+    pub enabled: bool,
+    pub max_attempts: u32,
+    pub base_delay_sec: u32,
+    pub max_total_delay_sec: u32,
+}
+
+impl Retry {
+    pub fn default() -> Self {
+        Self {
+            enabled: true,
+            max_attempts: 5,
+            base_delay_sec: 30,
+            max_total_delay_sec: 30 * 60,
+        }
+    }
+}
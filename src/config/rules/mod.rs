@@ -0,0 +1,169 @@
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    pub enabled: bool,
+    pub conditions: Vec<Condition>,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    MapIn { map_ids: Vec<u32> },
+    MapCategory { category: MapCategory },
+    TimeWindow { start: NaiveTime, end: NaiveTime },
+    Account { name: String },
+    Character { name: String },
+    SessionLongerThan { minutes: u32 },
+    InCombat { in_combat: bool },
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MapCategory {
+    Kp,
+    RetainRefresh,
+    Other,
+}
+
+// one-off actions apply when a rule starts matching, Hold and Suppress while it matches
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    RefreshNow,
+    ScheduleIn { minutes: u32 },
+    ScheduleOnRelease,
+    Hold,
+    Suppress,
+}
+
+impl Condition {
+    pub const LABELS: [&'static str; 7] = [
+        "Map in list",
+        "Map category",
+        "Time window",
+        "Account",
+        "Character",
+        "Session longer than",
+        "Combat state",
+    ];
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => Condition::MapIn { map_ids: vec![] },
+            1 => Condition::MapCategory {
+                category: MapCategory::Kp,
+            },
+            2 => Condition::TimeWindow {
+                start: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            },
+            3 => Condition::Account {
+                name: "".to_string(),
+            },
+            4 => Condition::Character {
+                name: "".to_string(),
+            },
+            5 => Condition::SessionLongerThan { minutes: 60 },
+            _ => Condition::InCombat { in_combat: false },
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        let index = match self {
+            Condition::MapIn { .. } => 0,
+            Condition::MapCategory { .. } => 1,
+            Condition::TimeWindow { .. } => 2,
+            Condition::Account { .. } => 3,
+            Condition::Character { .. } => 4,
+            Condition::SessionLongerThan { .. } => 5,
+            Condition::InCombat { .. } => 6,
+        };
+        Self::LABELS[index]
+    }
+}
+
+impl MapCategory {
+    pub const LABELS: [&'static str; 3] = ["KP maps", "Refresh retaining maps", "Other maps"];
+    const VALUES: [MapCategory; 3] = [
+        MapCategory::Kp,
+        MapCategory::RetainRefresh,
+        MapCategory::Other,
+    ];
+
+    pub fn index(&self) -> usize {
+        Self::VALUES
+            .iter()
+            .position(|value| value == self)
+            .unwrap_or(0)
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        Self::VALUES[index.min(Self::VALUES.len() - 1)]
+    }
+}
+
+impl Action {
+    pub const LABELS: [&'static str; 5] = [
+        "Refresh now",
+        "Schedule in N minutes",
+        "Schedule when no longer held",
+        "Hold scheduled refresh",
+        "Suppress scheduled refresh",
+    ];
+
+    pub fn index(&self) -> usize {
+        match self {
+            Action::RefreshNow => 0,
+            Action::ScheduleIn { .. } => 1,
+            Action::ScheduleOnRelease => 2,
+            Action::Hold => 3,
+            Action::Suppress => 4,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            0 => Action::RefreshNow,
+            1 => Action::ScheduleIn { minutes: 10 },
+            2 => Action::ScheduleOnRelease,
+            3 => Action::Hold,
+            _ => Action::Suppress,
+        }
+    }
+}
+
+impl Rule {
+    pub fn default() -> Self {
+        Self {
+            name: "New rule".to_string(),
+            enabled: true,
+            conditions: vec![],
+            action: Action::Hold,
+        }
+    }
+}
+
+pub fn default_rules() -> Vec<Rule> {
+    vec![
+        Rule {
+            name: "Schedule refresh on KP maps".to_string(),
+            enabled: true,
+            conditions: vec![Condition::MapCategory {
+                category: MapCategory::Kp,
+            }],
+            action: Action::ScheduleOnRelease,
+        },
+        Rule {
+            name: "Hold refresh on retaining maps".to_string(),
+            enabled: true,
+            conditions: vec![Condition::MapCategory {
+                category: MapCategory::RetainRefresh,
+            }],
+            action: Action::Hold,
+        },
+    ]
+}
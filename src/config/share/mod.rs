@@ -0,0 +1,351 @@
+use crate::addon::{Addon, VERSION};
+use crate::config::calendar::Calendar;
+use crate::config::notifications::Notifications;
+use crate::config::presets::Preset;
+use crate::config::rules::Rule;
+use crate::config::squad_triggers::SquadTriggers;
+use crate::config::weekly_reset::WeeklyReset;
+use crate::config::Config;
+use crate::state::State;
+use ::function_name::named;
+use log::{error, info};
+use nexus::alert::send_alert;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::thread;
+
+const FORMAT_NAME: &str = "kpsync";
+const FORMAT_VERSION: u32 = 1;
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SharedSettings {
+    pub version: String,
+    pub kp_map_ids: Vec<u32>,
+    pub retain_refresh_map_ids: Vec<u32>,
+    pub notifications: Notifications,
+    #[serde(alias = "scheduling_on_map_enter_enabled")]
+    pub scheduling_rules_enabled: bool,
+    pub refresh_only_on_kill: bool,
+    pub squad_triggers: SquadTriggers,
+    pub weekly_reset: WeeklyReset,
+    pub calendar: Calendar,
+    pub rules: Vec<Rule>,
+    pub presets: Vec<Preset>,
+}
+
+impl SharedSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            version: VERSION.to_string(),
+            kp_map_ids: config.kp_map_ids.clone(),
+            retain_refresh_map_ids: config.retain_refresh_map_ids.clone(),
+            notifications: config.notifications.clone(),
+            scheduling_rules_enabled: config.scheduling_rules_enabled,
+            refresh_only_on_kill: config.refresh_only_on_kill,
+            squad_triggers: config.squad_triggers.clone(),
+            weekly_reset: config.weekly_reset.clone(),
+            calendar: config.calendar.clone(),
+            rules: config.rules.clone(),
+            presets: config.presets.clone(),
+        }
+    }
+
+    pub fn apply(&self, config: &mut Config, state: &mut State) {
+        config.kp_map_ids = self.kp_map_ids.clone();
+        config.retain_refresh_map_ids = self.retain_refresh_map_ids.clone();
+        config.notifications = self.notifications.clone();
+        config.scheduling_rules_enabled = self.scheduling_rules_enabled;
+        config.refresh_only_on_kill = self.refresh_only_on_kill;
+        config.squad_triggers = self.squad_triggers.clone();
+        config.weekly_reset = self.weekly_reset.clone();
+        config.calendar = self.calendar.clone();
+        config.rules = self.rules.clone();
+        config.presets = self.presets.clone();
+        state.active_preset = None;
+        state.preset_base = None;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    InvalidFormat,
+    UnsupportedVersion(String),
+    ChecksumMismatch,
+    InvalidPayload(String),
+    Clipboard(String),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::InvalidFormat => write!(f, "clipboard does not contain kp_sync settings"),
+            ImportError::UnsupportedVersion(version) => {
+                write!(
+                    f,
+                    "settings format {} is not supported, update kp_sync",
+                    version
+                )
+            }
+            ImportError::ChecksumMismatch => write!(f, "settings are damaged (checksum mismatch)"),
+            ImportError::InvalidPayload(err) => write!(f, "settings could not be read ({})", err),
+            ImportError::Clipboard(err) => write!(f, "clipboard could not be read ({})", err),
+        }
+    }
+}
+
+pub fn export_settings(config: &Config) -> String {
+    let json = serde_json::to_vec(&SharedSettings::from_config(config))
+        .expect("failed to serialize settings");
+    format!(
+        "{}{}.{}.{:08x}",
+        FORMAT_NAME,
+        FORMAT_VERSION,
+        base64_encode(&json),
+        checksum(&json)
+    )
+}
+
+pub fn import_settings(text: &str) -> Result<SharedSettings, ImportError> {
+    let mut parts = text.trim().split('.');
+    let (Some(header), Some(payload), Some(expected_checksum), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(ImportError::InvalidFormat);
+    };
+    let version = header
+        .strip_prefix(FORMAT_NAME)
+        .ok_or(ImportError::InvalidFormat)?;
+    if version != FORMAT_VERSION.to_string() {
+        return Err(ImportError::UnsupportedVersion(version.to_string()));
+    }
+    let json = base64_decode(payload).ok_or(ImportError::InvalidFormat)?;
+    if format!("{:08x}", checksum(&json)) != expected_checksum {
+        return Err(ImportError::ChecksumMismatch);
+    }
+    serde_json::from_slice(&json).map_err(|err| ImportError::InvalidPayload(err.to_string()))
+}
+
+pub fn settings_diff(current: &SharedSettings, imported: &SharedSettings) -> Vec<String> {
+    let mut diff = vec![];
+    map_list_diff(
+        &mut diff,
+        "KP maps",
+        &current.kp_map_ids,
+        &imported.kp_map_ids,
+    );
+    map_list_diff(
+        &mut diff,
+        "Refresh retaining maps",
+        &current.retain_refresh_map_ids,
+        &imported.retain_refresh_map_ids,
+    );
+    let current = serde_json::to_value(current).unwrap_or(Value::Null);
+    let imported = serde_json::to_value(imported).unwrap_or(Value::Null);
+    if let (Some(current), Some(imported)) = (current.as_object(), imported.as_object()) {
+        for (key, imported_value) in imported {
+            if matches!(
+                key.as_str(),
+                "version" | "kp_map_ids" | "retain_refresh_map_ids"
+            ) {
+                continue;
+            }
+            if current.get(key) != Some(imported_value) {
+                diff.push(format!("{}: {}", key, value_text(imported_value)));
+            }
+        }
+    }
+    diff
+}
+
+fn map_list_diff(diff: &mut Vec<String>, label: &str, current: &[u32], imported: &[u32]) {
+    let added: Vec<String> = imported
+        .iter()
+        .filter(|id| !current.contains(id))
+        .map(|id| id.to_string())
+        .collect();
+    let removed: Vec<String> = current
+        .iter()
+        .filter(|id| !imported.contains(id))
+        .map(|id| id.to_string())
+        .collect();
+    if !added.is_empty() {
+        diff.push(format!("{}: + {}", label, added.join(", ")));
+    }
+    if !removed.is_empty() {
+        diff.push(format!("{}: - {}", label, removed.join(", ")));
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::Array(values) => format!("{} entries", values.len()),
+        Value::Object(_) => "changed".to_string(),
+        value => value.to_string(),
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(0x811c9dc5, |hash, byte| {
+        (hash ^ *byte as u32).wrapping_mul(0x01000193)
+    })
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk.iter().enumerate().fold(0u32, |buffer, (i, byte)| {
+            buffer | ((*byte as u32) << (16 - 8 * i))
+        });
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64_ALPHABET[((buffer >> (18 - 6 * i)) & 0x3f) as usize] as char);
+        }
+    }
+    encoded
+}
+
+fn base64_decode(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(text.len() * 3 / 4);
+    for chunk in text.as_bytes().chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let mut buffer = 0u32;
+        for (i, char) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|c| c == char)? as u32;
+            buffer |= index << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((buffer >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[named]
+pub fn export_settings_to_clipboard() {
+    Addon::threads().push(thread::spawn(|| {
+        let mut addon = Addon::lock();
+        let text = export_settings(&addon.config);
+        match addon.context.clipboard.set_text(text.as_str()) {
+            Ok(_) => send_alert("Settings copied to clipboard."),
+            Err(err) => error!("[{}] error copying settings: {}", function_name!(), err),
+        }
+    }));
+}
+
+#[named]
+pub fn import_settings_from_clipboard() {
+    Addon::threads().push(thread::spawn(|| {
+        let mut addon = Addon::lock();
+        let text = match addon.context.clipboard.get_text() {
+            Ok(text) => text,
+            Err(err) => {
+                error!("[{}] error reading clipboard: {}", function_name!(), err);
+                addon.context.ui.settings_import =
+                    Some(Err(ImportError::Clipboard(err.to_string())));
+                return;
+            }
+        };
+        let import = import_settings(&text).map(|settings| {
+            let diff = settings_diff(&SharedSettings::from_config(&addon.config), &settings);
+            info!(
+                "[{}] settings from {} read, {} differences",
+                function_name!(),
+                settings.version,
+                diff.len()
+            );
+            (settings, diff)
+        });
+        addon.context.ui.settings_import = Some(import);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exported() -> String {
+        export_settings(&Config::default())
+    }
+
+    fn replace_part(text: &str, index: usize, part: &str) -> String {
+        let mut parts: Vec<&str> = text.split('.').collect();
+        parts[index] = part;
+        parts.join(".")
+    }
+
+    #[test]
+    fn base64_round_trip_for_every_length() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        for len in 0..=9 {
+            let input = &bytes[200 - len..200];
+            let encoded = base64_encode(input);
+            assert_eq!(encoded.len(), (len * 4).div_ceil(3));
+            assert_eq!(base64_decode(&encoded).as_deref(), Some(input));
+        }
+    }
+
+    #[test]
+    fn round_trip_keeps_settings() {
+        let config = Config::default();
+        let settings = import_settings(&export_settings(&config)).unwrap();
+        assert_eq!(settings, SharedSettings::from_config(&config));
+    }
+
+    #[test]
+    fn flipped_character_fails_checksum() {
+        let text = exported();
+        let payload = text.split('.').nth(1).unwrap();
+        let mut flipped = payload.as_bytes().to_vec();
+        flipped[10] = if flipped[10] == b'A' { b'B' } else { b'A' };
+        let text = replace_part(&text, 1, std::str::from_utf8(&flipped).unwrap());
+        assert_eq!(import_settings(&text), Err(ImportError::ChecksumMismatch));
+    }
+
+    #[test]
+    fn wrong_version_prefix_is_rejected() {
+        let text = exported();
+        assert_eq!(
+            import_settings(&replace_part(&text, 0, "kpsync2")),
+            Err(ImportError::UnsupportedVersion("2".to_string()))
+        );
+        assert_eq!(
+            import_settings(&replace_part(&text, 0, "other1")),
+            Err(ImportError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn truncated_payload_is_rejected() {
+        let text = exported();
+        assert_eq!(
+            import_settings(&text[..text.len() / 2]),
+            Err(ImportError::InvalidFormat)
+        );
+        let payload = text.split('.').nth(1).unwrap();
+        let truncated = replace_part(&text, 1, &payload[..payload.len() - 4]);
+        assert_eq!(
+            import_settings(&truncated),
+            Err(ImportError::ChecksumMismatch)
+        );
+    }
+
+    #[test]
+    fn apply_resets_active_preset() {
+        let mut config = Config::default();
+        let mut state = State {
+            active_preset: Some("Raid night".to_string()),
+            preset_base: Some(Preset::capture("", &config)),
+            ..State::default()
+        };
+        let mut settings = SharedSettings::from_config(&config);
+        settings.presets.clear();
+        settings.apply(&mut config, &mut state);
+        assert!(config.presets.is_empty());
+        assert_eq!(state.active_preset, None);
+        assert_eq!(state.preset_base, None);
+    }
+}
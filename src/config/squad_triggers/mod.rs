@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SquadTriggers {
+    pub refresh_on_squad_leave: bool,
+    pub refresh_on_squad_size_drop: bool,
+    pub squad_size_threshold: u32,
+}
+
+impl SquadTriggers {
+    pub fn default() -> Self {
+        Self {
+            refresh_on_squad_leave: false,
+            refresh_on_squad_size_drop: false,
+            squad_size_threshold: 5,
+        }
+    }
+}
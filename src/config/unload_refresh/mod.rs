@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UnloadRefresh {
+    pub enabled: bool,
+    pub time_budget_ms: u32,
+}
+
+impl UnloadRefresh {
+    pub fn default() -> Self {
+        Self {
+            enabled: false,
+            time_budget_ms: 2000,
+        }
+    }
+}
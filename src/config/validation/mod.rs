@@ -0,0 +1,342 @@
+use crate::addon::Addon;
+use crate::config::network::valid_proxy;
+use crate::config::{default_date_format, valid_kp_id, Config};
+use crate::render::valid_date_format;
+use crate::state::State;
+use nexus::alert::send_alert;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigIssue {
+    InvalidMainId(String),
+    InvalidLinkedId(String),
+    DuplicateKpMap(u32),
+    DuplicateRetainMap(u32),
+    MapInBothLists(u32),
+    UnknownMap(u32),
+    InvalidDateFormat(String),
+    InvalidProxy(String),
+}
+
+impl ConfigIssue {
+    pub fn severity(&self) -> Severity {
+        match self {
+            ConfigIssue::InvalidMainId(_) => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+
+    pub fn fix_label(&self) -> Option<&'static str> {
+        match self {
+            ConfigIssue::InvalidMainId(_) => None,
+            ConfigIssue::InvalidLinkedId(_) => Some("Remove linked id"),
+            ConfigIssue::DuplicateKpMap(_) | ConfigIssue::DuplicateRetainMap(_) => {
+                Some("Remove duplicates")
+            }
+            ConfigIssue::MapInBothLists(_) => Some("Remove from refresh retaining maps"),
+            ConfigIssue::UnknownMap(_) => Some("Remove map"),
+            ConfigIssue::InvalidDateFormat(_) => Some("Reset date format"),
+            ConfigIssue::InvalidProxy(_) => Some("Clear proxy"),
+        }
+    }
+
+    pub fn fix(&self, config: &mut Config, state: &mut State) {
+        match self {
+            ConfigIssue::InvalidMainId(_) => {}
+            ConfigIssue::InvalidLinkedId(id) => {
+                if let Some(linked_ids) = state.linked_ids.as_mut() {
+                    linked_ids.retain(|linked_id| linked_id != id);
+                }
+            }
+            ConfigIssue::DuplicateKpMap(map_id) => dedup_map(&mut config.kp_map_ids, *map_id),
+            ConfigIssue::DuplicateRetainMap(map_id) => {
+                dedup_map(&mut config.retain_refresh_map_ids, *map_id)
+            }
+            ConfigIssue::MapInBothLists(map_id) => {
+                config.retain_refresh_map_ids.retain(|id| id != map_id)
+            }
+            ConfigIssue::UnknownMap(map_id) => {
+                config.kp_map_ids.retain(|id| id != map_id);
+                config.retain_refresh_map_ids.retain(|id| id != map_id);
+            }
+            ConfigIssue::InvalidDateFormat(_) => config.date_format = default_date_format(),
+            ConfigIssue::InvalidProxy(_) => config.network.proxy.clear(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigIssue::InvalidMainId(id) => write!(f, "KP id \"{}\" has invalid format", id),
+            ConfigIssue::InvalidLinkedId(id) => {
+                write!(f, "Linked KP id \"{}\" has invalid format", id)
+            }
+            ConfigIssue::DuplicateKpMap(map_id) => {
+                write!(f, "Map {} is listed more than once in KP maps", map_id)
+            }
+            ConfigIssue::DuplicateRetainMap(map_id) => write!(
+                f,
+                "Map {} is listed more than once in refresh retaining maps",
+                map_id
+            ),
+            ConfigIssue::MapInBothLists(map_id) => write!(
+                f,
+                "Map {} is both a KP map and a refresh retaining map",
+                map_id
+            ),
+            ConfigIssue::UnknownMap(map_id) => write!(f, "Map {} is not a known GW2 map", map_id),
+            ConfigIssue::InvalidDateFormat(format) => {
+                write!(f, "Date format \"{}\" is invalid", format)
+            }
+            ConfigIssue::InvalidProxy(proxy) => write!(
+                f,
+                "Proxy \"{}\" is invalid, requests are sent without it",
+                proxy
+            ),
+        }
+    }
+}
+
+pub fn validate(
+    config: &Config,
+    state: &State,
+    map_names: &HashMap<String, String>,
+) -> Vec<ConfigIssue> {
+    let mut issues = vec![];
+    let main_id = &config.kp_identifiers.main_id;
+    if !main_id.is_empty() && !valid_kp_id(main_id) {
+        issues.push(ConfigIssue::InvalidMainId(main_id.clone()));
+    }
+    for linked_id in state.linked_ids.iter().flatten() {
+        if !valid_kp_id(linked_id) {
+            issues.push(ConfigIssue::InvalidLinkedId(linked_id.clone()));
+        }
+    }
+    for map_id in duplicates(&config.kp_map_ids) {
+        issues.push(ConfigIssue::DuplicateKpMap(map_id));
+    }
+    for map_id in duplicates(&config.retain_refresh_map_ids) {
+        issues.push(ConfigIssue::DuplicateRetainMap(map_id));
+    }
+    let mut checked_maps: Vec<u32> = vec![];
+    for map_id in config
+        .kp_map_ids
+        .iter()
+        .chain(&config.retain_refresh_map_ids)
+    {
+        if checked_maps.contains(map_id) {
+            continue;
+        }
+        checked_maps.push(*map_id);
+        if config.kp_map_ids.contains(map_id) && config.retain_refresh_map_ids.contains(map_id) {
+            issues.push(ConfigIssue::MapInBothLists(*map_id));
+        }
+        if !map_names.is_empty() && !map_names.contains_key(&map_id.to_string()) {
+            issues.push(ConfigIssue::UnknownMap(*map_id));
+        }
+    }
+    if !valid_date_format(&config.date_format) {
+        issues.push(ConfigIssue::InvalidDateFormat(config.date_format.clone()));
+    }
+    let proxy = &config.network.proxy;
+    if !proxy.is_empty() && !valid_proxy(proxy) {
+        issues.push(ConfigIssue::InvalidProxy(proxy.clone()));
+    }
+    issues.sort_by_key(|issue| std::cmp::Reverse(issue.severity()));
+    issues
+}
+
+fn duplicates(map_ids: &[u32]) -> Vec<u32> {
+    let mut seen = vec![];
+    let mut duplicates = vec![];
+    for map_id in map_ids {
+        if seen.contains(map_id) {
+            if !duplicates.contains(map_id) {
+                duplicates.push(*map_id);
+            }
+        } else {
+            seen.push(*map_id);
+        }
+    }
+    duplicates
+}
+
+fn dedup_map(map_ids: &mut Vec<u32>, map_id: u32) {
+    let mut seen = false;
+    map_ids.retain(|id| {
+        if *id != map_id {
+            return true;
+        }
+        let keep = !seen;
+        seen = true;
+        keep
+    });
+}
+
+impl Addon {
+    pub fn validate_config(&mut self) {
+        self.context.config_issues =
+            validate(&self.config, &self.state, &self.context.ui.map_names);
+    }
+
+    pub fn report_config_issues(&self) {
+        let issues = &self.context.config_issues;
+        if issues.is_empty() {
+            return;
+        }
+        let errors = issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+            .count();
+        send_alert(format!(
+            "Configuration has {} issue(s) ({} error(s)), see the options window",
+            issues.len(),
+            errors
+        ));
+    }
+
+    pub fn fix_config_issue(&mut self, index: usize) {
+        if let Some(issue) = self.context.config_issues.get(index).cloned() {
+            issue.fix(&mut self.config, &mut self.state);
+            self.validate_config();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn known_maps(config: &Config) -> HashMap<String, String> {
+        config
+            .kp_map_ids
+            .iter()
+            .chain(&config.retain_refresh_map_ids)
+            .map(|map_id| (map_id.to_string(), format!("Map {}", map_id)))
+            .collect()
+    }
+
+    fn issues(config: &Config, state: &State) -> Vec<ConfigIssue> {
+        validate(config, state, &known_maps(config))
+    }
+
+    fn fixed(issue: &ConfigIssue, config: &mut Config, state: &mut State) -> Vec<ConfigIssue> {
+        issue.fix(config, state);
+        issues(config, state)
+    }
+
+    #[test]
+    fn default_config_has_no_issues() {
+        assert!(issues(&Config::default(), &State::default()).is_empty());
+    }
+
+    #[test]
+    fn reports_and_fixes_duplicate_maps() {
+        let mut config = Config::default();
+        let mut state = State::default();
+        config.kp_map_ids.push(config.kp_map_ids[0]);
+        config
+            .retain_refresh_map_ids
+            .push(config.retain_refresh_map_ids[0]);
+        let kp_issue = ConfigIssue::DuplicateKpMap(config.kp_map_ids[0]);
+        let retain_issue = ConfigIssue::DuplicateRetainMap(config.retain_refresh_map_ids[0]);
+        let found = issues(&config, &state);
+        assert!(found.contains(&kp_issue) && found.contains(&retain_issue));
+
+        let remaining = fixed(&kp_issue, &mut config, &mut state);
+        assert_eq!(remaining, vec![retain_issue.clone()]);
+        assert!(fixed(&retain_issue, &mut config, &mut state).is_empty());
+        assert_eq!(config.kp_map_ids, Config::default().kp_map_ids);
+        assert_eq!(
+            config.retain_refresh_map_ids,
+            Config::default().retain_refresh_map_ids
+        );
+    }
+
+    #[test]
+    fn reports_and_fixes_map_in_both_lists() {
+        let mut config = Config::default();
+        let mut state = State::default();
+        let map_id = config.kp_map_ids[0];
+        config.retain_refresh_map_ids.push(map_id);
+        let issue = ConfigIssue::MapInBothLists(map_id);
+        assert_eq!(issues(&config, &state), vec![issue.clone()]);
+        assert!(fixed(&issue, &mut config, &mut state).is_empty());
+        assert!(config.kp_map_ids.contains(&map_id));
+        assert!(!config.retain_refresh_map_ids.contains(&map_id));
+    }
+
+    #[test]
+    fn reports_unknown_maps_only_once_names_are_loaded() {
+        let mut config = Config::default();
+        let mut state = State::default();
+        let map_names = known_maps(&config);
+        config.kp_map_ids.push(999_999);
+        assert!(validate(&config, &state, &HashMap::new()).is_empty());
+        let issue = ConfigIssue::UnknownMap(999_999);
+        assert_eq!(validate(&config, &state, &map_names), vec![issue.clone()]);
+        issue.fix(&mut config, &mut state);
+        assert!(validate(&config, &state, &map_names).is_empty());
+    }
+
+    #[test]
+    fn reports_and_fixes_invalid_linked_ids() {
+        let mut config = Config::default();
+        let mut state = State {
+            linked_ids: Some(vec!["Alt.5678".to_string(), "not valid!".to_string()]),
+            ..State::default()
+        };
+        let issue = ConfigIssue::InvalidLinkedId("not valid!".to_string());
+        assert_eq!(issues(&config, &state), vec![issue.clone()]);
+        assert!(fixed(&issue, &mut config, &mut state).is_empty());
+        assert_eq!(state.linked_ids, Some(vec!["Alt.5678".to_string()]));
+    }
+
+    #[test]
+    fn invalid_main_id_is_an_error_without_fix() {
+        let mut config = Config::default();
+        config.kp_identifiers.main_id = "not valid!".to_string();
+        let found = issues(&config, &State::default());
+        assert_eq!(
+            found,
+            vec![ConfigIssue::InvalidMainId("not valid!".to_string())]
+        );
+        assert_eq!(found[0].severity(), Severity::Error);
+        assert_eq!(found[0].fix_label(), None);
+    }
+
+    #[test]
+    fn each_fix_clears_its_own_issue() {
+        let mut config = Config::default();
+        let mut state = State {
+            linked_ids: Some(vec!["not valid!".to_string()]),
+            ..State::default()
+        };
+        let map_names = known_maps(&config);
+        config.kp_map_ids.push(config.kp_map_ids[0]);
+        config
+            .retain_refresh_map_ids
+            .push(config.retain_refresh_map_ids[0]);
+        config.retain_refresh_map_ids.push(config.kp_map_ids[1]);
+        config.kp_map_ids.push(999_999);
+        config.date_format = "%Q".to_string();
+        config.network.proxy = "not a proxy".to_string();
+
+        let found = validate(&config, &state, &map_names);
+        assert_eq!(found.len(), 7);
+        for issue in found {
+            assert!(issue.fix_label().is_some());
+            issue.fix(&mut config, &mut state);
+            assert!(!validate(&config, &state, &map_names).contains(&issue));
+        }
+        assert!(validate(&config, &state, &map_names).is_empty());
+    }
+}
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WeeklyReset {
+    pub refresh_before_reset: bool,
+    pub minutes_before_reset: u32,
+    pub refresh_after_first_session: bool,
+}
+
+impl WeeklyReset {
+    pub fn default() -> Self {
+        Self {
+            refresh_before_reset: false,
+            minutes_before_reset: 15,
+            refresh_after_first_session: false,
+        }
+    }
+}
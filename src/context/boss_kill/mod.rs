@@ -0,0 +1,9 @@
+use chrono::{DateTime, Utc};
+
+#[derive(Clone, Debug)]
+pub struct BossKill {
+    pub species_id: u64,
+    pub name: String,
+    pub date: DateTime<Utc>,
+    pub refreshed: bool,
+}
@@ -0,0 +1,21 @@
+use chrono::{DateTime, Local};
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+#[derive(Clone, Debug)]
+pub struct EvtcWatcherContext {
+    pub started: SystemTime,
+    pub processed: HashSet<PathBuf>,
+    pub last_poll_date: DateTime<Local>,
+}
+
+impl Default for EvtcWatcherContext {
+    fn default() -> Self {
+        Self {
+            started: SystemTime::now(),
+            processed: HashSet::new(),
+            last_poll_date: Local::now(),
+        }
+    }
+}
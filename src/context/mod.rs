@@ -1,19 +1,30 @@
+pub mod boss_kill;
+pub mod evtc_watcher;
 pub mod scheduled_refresh;
-mod ui;
+pub mod ui;
 mod clipboard;
 
 use crate::addon::Addon;
 use crate::api::kp::kp_response::KpResponse;
+use crate::api::kp::refresh::retry::RetryAttempts;
+use crate::combat::EncounterTracker;
+use crate::context::boss_kill::BossKill;
+use crate::context::evtc_watcher::EvtcWatcherContext;
 use crate::context::scheduled_refresh::ScheduledRefresh;
 use crate::context::ui::UiContext;
 use nexus::data_link::get_mumble_link;
-use nexus::data_link::mumble::MumblePtr;
+use nexus::data_link::mumble::{Identity, MumblePtr};
 use std::sync::MutexGuard;
-use chrono::{DateTime, Local};
+use std::time::SystemTime;
+use chrono::{DateTime, Local, Utc};
 use nexus::data_link::rtapi::read_rtapi;
 use crate::context::clipboard::CustomClipboard;
 use nexus::rtapi::data::RealTimeData;
+use crate::config::validation::ConfigIssue;
 use crate::config::Config;
+use crate::lease::{instance_id, Lease};
+use crate::state::State;
+use crate::session::{load_sessions, RaidSession};
 
 #[derive(Debug, Clone)]
 pub struct Context {
@@ -21,7 +32,8 @@ pub struct Context {
     pub linked_kp_responses: Vec<(String, KpResponse)>,
     pub mumble: Option<MumblePtr>,
     pub scheduled_refresh: Option<ScheduledRefresh>,
-    pub on_kp_map: bool,
+    pub refresh_held: bool,
+    pub matching_rules: Vec<usize>,
     pub run_background_thread: bool,
     pub refresh_in_progress: bool,
     pub ui: UiContext,
@@ -31,7 +43,27 @@ pub struct Context {
     pub last_config_save_date: DateTime<Local>,
     pub last_refresh_daemon_tick_date: DateTime<Local>,
     pub first_map_tick: bool,
-    pub saved_config: Option<Config>
+    pub saved_config: Option<Config>,
+    pub saved_state: Option<State>,
+    pub config_file_modified: Option<SystemTime>,
+    pub rejected_config_file_modified: Option<SystemTime>,
+    pub config_issues: Vec<ConfigIssue>,
+    pub instance_id: String,
+    pub other_client_lease: Option<Lease>,
+    pub retry_attempts: Option<RetryAttempts>,
+    pub cooldown_fallback_alerted: bool,
+    pub kp_map_visited: bool,
+    pub in_squad: bool,
+    pub squad_member_count: u32,
+    pub encounter: EncounterTracker,
+    pub kills: Vec<BossKill>,
+    pub evtc_watcher: EvtcWatcherContext,
+    pub session: Option<RaidSession>,
+    pub sessions: Vec<RaidSession>,
+    pub last_calendar_check_date: DateTime<Local>,
+    pub identity: Option<Identity>,
+    pub in_combat: bool,
+    pub pending_refresh_restored: bool,
 }
 
 impl Default for Context {
@@ -40,7 +72,8 @@ impl Default for Context {
             main_kp_response: KpResponse::Unavailable,
             linked_kp_responses: vec![],
             mumble: get_mumble_link(),
-            on_kp_map: false,
+            refresh_held: false,
+            matching_rules: vec![],
             scheduled_refresh: None,
             run_background_thread: true,
             refresh_in_progress: false,
@@ -51,17 +84,69 @@ impl Default for Context {
             last_config_save_date: Local::now(),
             last_refresh_daemon_tick_date: Local::now(),
             first_map_tick: true,
-            saved_config: None
+            saved_config: None,
+            saved_state: None,
+            config_file_modified: None,
+            rejected_config_file_modified: None,
+            config_issues: vec![],
+            instance_id: instance_id(),
+            other_client_lease: None,
+            retry_attempts: None,
+            cooldown_fallback_alerted: false,
+            kp_map_visited: false,
+            in_squad: false,
+            squad_member_count: 0,
+            encounter: EncounterTracker::default(),
+            kills: vec![],
+            evtc_watcher: EvtcWatcherContext::default(),
+            session: None,
+            sessions: vec![],
+            last_calendar_check_date: Local::now(),
+            identity: None,
+            in_combat: false,
+            pending_refresh_restored: false,
         }
     }
 }
 impl Context {
     pub fn valid(&mut self, main_kp_id: &str) -> bool {
         match &self.main_kp_response {
-            KpResponse::InvalidId(invalid_id) => invalid_id != main_kp_id,
+            KpResponse::InvalidId(invalid_id, _) => invalid_id != main_kp_id,
             _ => true,
         }
     }
+    pub fn record_kill(&mut self, species_id: u64, name: &str) {
+        self.kills.push(BossKill {
+            species_id,
+            name: name.to_string(),
+            date: Utc::now(),
+            refreshed: false,
+        });
+    }
+
+    pub fn kill_names_since(&self, date: DateTime<Utc>) -> Vec<String> {
+        self.kills
+            .iter()
+            .filter(|kill| kill.date >= date)
+            .map(|kill| kill.name.clone())
+            .collect()
+    }
+
+    pub fn has_unrefreshed_kills(&self) -> bool {
+        self.kills.iter().any(|kill| !kill.refreshed)
+    }
+
+    pub fn take_unrefreshed_kill_names(&mut self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        for kill in self.kills.iter_mut().filter(|kill| !kill.refreshed) {
+            kill.refreshed = true;
+            if !names.contains(&kill.name) {
+                names.push(kill.name.clone());
+            }
+        }
+        names
+    }
+
     pub unsafe fn update_rtapi(&mut self) {
         if let Some(rtapi) = read_rtapi() {
             if rtapi.game_build != 0 {
@@ -75,4 +160,6 @@ impl Context {
 
 pub fn init_context(addon: &mut MutexGuard<Addon>) {
     addon.context.ui.previous_main_id = addon.config.kp_identifiers.main_id.clone();
+    addon.context.sessions = load_sessions();
+    addon.context.config_file_modified = Config::file_modified();
 }
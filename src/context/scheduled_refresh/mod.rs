@@ -1,11 +1,26 @@
 use crate::render::countdown_str;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", content = "date", rename_all = "snake_case")]
 pub enum ScheduledRefresh {
     OnNormalMapEnter,
-    OnTime(DateTime<Local>),
+    OnTime(DateTime<Utc>),
+}
+
+impl ScheduledRefresh {
+    // a pending cooldown must not be cut short by a trigger
+    pub fn keep_later(current: Option<ScheduledRefresh>, date: DateTime<Utc>) -> ScheduledRefresh {
+        match current {
+            Some(ScheduledRefresh::OnTime(scheduled)) => {
+                ScheduledRefresh::OnTime(scheduled.max(date))
+            }
+            Some(refresh) => refresh,
+            None => ScheduledRefresh::OnTime(date),
+        }
+    }
 }
 
 impl fmt::Display for ScheduledRefresh {
@@ -13,9 +28,42 @@ impl fmt::Display for ScheduledRefresh {
         match self {
             ScheduledRefresh::OnNormalMapEnter => write!(f, "on normal map enter"),
             ScheduledRefresh::OnTime(time) => {
-                let delta = time.signed_duration_since(Local::now());
+                let delta = time.signed_duration_since(Utc::now());
                 write!(f, "in {}", countdown_str(delta))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+
+    #[test]
+    fn keeps_later_of_two_times() {
+        let now = Utc::now();
+        let cooldown = now + TimeDelta::minutes(30);
+        assert_eq!(
+            ScheduledRefresh::keep_later(Some(ScheduledRefresh::OnTime(cooldown)), now),
+            ScheduledRefresh::OnTime(cooldown)
+        );
+        assert_eq!(
+            ScheduledRefresh::keep_later(Some(ScheduledRefresh::OnTime(now)), cooldown),
+            ScheduledRefresh::OnTime(cooldown)
+        );
+    }
+
+    #[test]
+    fn schedules_when_nothing_is_pending() {
+        let now = Utc::now();
+        assert_eq!(
+            ScheduledRefresh::keep_later(None, now),
+            ScheduledRefresh::OnTime(now)
+        );
+        assert_eq!(
+            ScheduledRefresh::keep_later(Some(ScheduledRefresh::OnNormalMapEnter), now),
+            ScheduledRefresh::OnNormalMapEnter
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::config::share::{ImportError, SharedSettings};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
@@ -7,6 +8,9 @@ pub struct UiContext {
     pub new_retain_map_search_term: String,
     pub errors: Errors,
     pub map_names: HashMap<String, String>,
+    pub new_rule_map_id: i32,
+    pub new_condition_index: usize,
+    pub settings_import: Option<Result<(SharedSettings, Vec<String>), ImportError>>,
 }
 
 #[derive(Clone, Debug)]
@@ -22,6 +26,9 @@ impl Default for UiContext {
             new_retain_map_search_term: "".to_string(),
             errors: Errors::default(),
             map_names: HashMap::new(),
+            new_rule_map_id: 0,
+            new_condition_index: 0,
+            settings_import: None,
         }
     }
 }
@@ -0,0 +1,144 @@
+pub mod watcher;
+
+use std::fs;
+use std::io::{Cursor, Read};
+use std::path::Path;
+
+const HEADER_SIZE: usize = 16;
+const AGENT_SIZE: usize = 96;
+const SKILL_SIZE: usize = 68;
+const EVENT_SIZE: usize = 64;
+const EVENT_STATECHANGE_OFFSET: usize = 56;
+const STATECHANGE_REWARD: u8 = 19;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvtcHeader {
+    pub build_date: String,
+    pub revision: u8,
+    pub species_id: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvtcLog {
+    pub header: EvtcHeader,
+    pub success: bool,
+}
+
+pub fn is_evtc_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            extension.eq_ignore_ascii_case("evtc") || extension.eq_ignore_ascii_case("zevtc")
+        })
+}
+
+pub fn read_log(path: &Path) -> Option<EvtcLog> {
+    let bytes = fs::read(path).ok()?;
+    let compressed = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zevtc"));
+    if compressed {
+        parse_log(&decompress(bytes)?)
+    } else {
+        parse_log(&bytes)
+    }
+}
+
+fn decompress(bytes: Vec<u8>) -> Option<Vec<u8>> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).ok()?;
+    let mut file = archive.by_index(0).ok()?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content).ok()?;
+    Some(content)
+}
+
+pub fn parse_header(bytes: &[u8]) -> Option<EvtcHeader> {
+    if bytes.len() < HEADER_SIZE || &bytes[0..4] != b"EVTC" {
+        return None;
+    }
+    Some(EvtcHeader {
+        build_date: String::from_utf8_lossy(&bytes[4..12]).to_string(),
+        revision: bytes[12],
+        species_id: u16::from_le_bytes([bytes[13], bytes[14]]),
+    })
+}
+
+pub fn parse_log(bytes: &[u8]) -> Option<EvtcLog> {
+    let header = parse_header(bytes)?;
+    let success = header.revision == 1 && events(bytes)?.any(is_reward_event);
+    Some(EvtcLog { header, success })
+}
+
+fn events(bytes: &[u8]) -> Option<std::slice::ChunksExact<'_, u8>> {
+    let agent_count = read_u32(bytes, HEADER_SIZE)? as usize;
+    let skill_count_offset = HEADER_SIZE + 4 + agent_count.checked_mul(AGENT_SIZE)?;
+    let skill_count = read_u32(bytes, skill_count_offset)? as usize;
+    let events_offset = skill_count_offset + 4 + skill_count.checked_mul(SKILL_SIZE)?;
+    bytes
+        .get(events_offset..)
+        .map(|events| events.chunks_exact(EVENT_SIZE))
+}
+
+fn is_reward_event(event: &[u8]) -> bool {
+    event[EVENT_STATECHANGE_OFFSET] == STATECHANGE_REWARD
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    let slice = bytes.get(offset..offset + 4)?;
+    Some(u32::from_le_bytes([slice[0], slice[1], slice[2], slice[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn sample(name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/evtc/samples")
+            .join(name)
+    }
+
+    #[test]
+    fn parses_header() {
+        let bytes = include_bytes!("samples/sabetha_success.evtc");
+        let header = parse_header(bytes).unwrap();
+        assert_eq!(header.build_date, "20240917");
+        assert_eq!(header.revision, 1);
+        assert_eq!(header.species_id, 15375);
+    }
+
+    #[test]
+    fn detects_successful_kill() {
+        let log = parse_log(include_bytes!("samples/sabetha_success.evtc")).unwrap();
+        assert!(log.success);
+    }
+
+    #[test]
+    fn detects_failed_attempt() {
+        let log = parse_log(include_bytes!("samples/sabetha_failure.evtc")).unwrap();
+        assert_eq!(log.header.species_id, 15375);
+        assert!(!log.success);
+    }
+
+    #[test]
+    fn reads_compressed_log() {
+        let log = read_log(&sample("qadim_the_peerless_success.zevtc")).unwrap();
+        assert_eq!(log.header.species_id, 22000);
+        assert!(log.success);
+    }
+
+    #[test]
+    fn rejects_truncated_log() {
+        assert!(parse_header(include_bytes!("samples/truncated.evtc")).is_none());
+        assert!(read_log(&sample("truncated.evtc")).is_none());
+    }
+
+    #[test]
+    fn recognizes_log_extensions() {
+        assert!(is_evtc_file(Path::new("20240917-201500.zevtc")));
+        assert!(is_evtc_file(Path::new("20240917-201500.EVTC")));
+        assert!(!is_evtc_file(Path::new("20240917-201500.html")));
+    }
+}
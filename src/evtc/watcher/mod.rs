@@ -0,0 +1,88 @@
+use crate::addon::Addon;
+use crate::combat::record_kill;
+use crate::evtc::{is_evtc_file, read_log};
+use function_name::named;
+use log::{debug, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const MAX_DIRECTORY_DEPTH: usize = 3;
+const FILE_SETTLE_DURATION: Duration = Duration::from_secs(2);
+
+#[named]
+pub fn poll_evtc_logs() {
+    let (directory, started, processed) = {
+        let addon = Addon::lock();
+        if !addon.config.evtc_watcher.enabled {
+            return;
+        }
+        (
+            PathBuf::from(&addon.config.evtc_watcher.directory),
+            addon.context.evtc_watcher.started,
+            addon.context.evtc_watcher.processed.clone(),
+        )
+    };
+
+    let mut new_logs = Vec::new();
+    collect_new_logs(
+        &directory,
+        started,
+        &processed,
+        MAX_DIRECTORY_DEPTH,
+        &mut new_logs,
+    );
+    for path in new_logs {
+        let log = read_log(&path);
+        let mut addon = Addon::lock();
+        addon.context.evtc_watcher.processed.insert(path.clone());
+        match log {
+            Some(log) if log.success => {
+                debug!("[{}] successful log {}", function_name!(), path.display());
+                record_kill(&mut addon, log.header.species_id as u64);
+            }
+            Some(_) => debug!(
+                "[{}] skipped unsuccessful log {}",
+                function_name!(),
+                path.display()
+            ),
+            None => warn!("[{}] could not parse {}", function_name!(), path.display()),
+        }
+    }
+}
+
+fn collect_new_logs(
+    directory: &Path,
+    since: SystemTime,
+    processed: &HashSet<PathBuf>,
+    depth: usize,
+    logs: &mut Vec<PathBuf>,
+) {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            if depth > 0 {
+                collect_new_logs(&path, since, processed, depth - 1, logs);
+            }
+            continue;
+        }
+        if !is_evtc_file(&path) || processed.contains(&path) {
+            continue;
+        }
+        if let Ok(modified) = metadata.modified() {
+            let settled = modified
+                .elapsed()
+                .is_ok_and(|elapsed| elapsed >= FILE_SETTLE_DURATION);
+            if modified > since && settled {
+                logs.push(path);
+            }
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use crate::config::config_dir;
+use crate::context::scheduled_refresh::ScheduledRefresh;
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+pub const MAIN_LEASE_TTL_SEC: i64 = 10;
+pub const LINKED_LEASE_TTL_SEC: i64 = 60;
+const LOCK_ATTEMPTS: u32 = 20;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(10);
+const STALE_LOCK_AGE: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Lease {
+    pub owner: String,
+    pub expires: DateTime<Utc>,
+    pub scheduled_refresh: Option<ScheduledRefresh>,
+    pub last_refresh_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LeaseStatus {
+    Acquired,
+    HeldBy(Lease),
+}
+
+pub fn lease_dir() -> PathBuf {
+    config_dir().join("leases")
+}
+
+pub fn instance_id() -> String {
+    let started = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    format!("{}-{}", std::process::id(), started)
+}
+
+pub fn acquire_lease(
+    dir: &Path,
+    kp_id: &str,
+    owner: &str,
+    ttl: TimeDelta,
+    scheduled_refresh: Option<ScheduledRefresh>,
+    last_refresh_date: Option<DateTime<Utc>>,
+) -> io::Result<LeaseStatus> {
+    fs::create_dir_all(dir)?;
+    let path = lease_path(dir, kp_id);
+    with_lock(&path, || {
+        let now = Utc::now();
+        if let Some(lease) = read_lease_file(&path) {
+            if lease.owner != owner && lease.expires > now {
+                return Ok(LeaseStatus::HeldBy(lease));
+            }
+        }
+        let lease = Lease {
+            owner: owner.to_string(),
+            expires: now + ttl,
+            scheduled_refresh,
+            last_refresh_date,
+        };
+        let temp_path = path.with_extension("lease.tmp");
+        fs::write(&temp_path, serde_json::to_vec(&lease)?)?;
+        fs::rename(&temp_path, &path)?;
+        Ok(LeaseStatus::Acquired)
+    })
+}
+
+pub fn release_lease(dir: &Path, kp_id: &str, owner: &str) -> io::Result<()> {
+    let path = lease_path(dir, kp_id);
+    with_lock(&path, || {
+        if read_lease_file(&path).is_some_and(|lease| lease.owner == owner) {
+            fs::remove_file(&path)?;
+        }
+        Ok(())
+    })
+}
+
+fn lease_path(dir: &Path, kp_id: &str) -> PathBuf {
+    let name: String = kp_id
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("{}.lease", name))
+}
+
+fn read_lease_file(path: &Path) -> Option<Lease> {
+    let content = fs::read(path).ok()?;
+    serde_json::from_slice(&content).ok()
+}
+
+fn with_lock<T>(path: &Path, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+    let lock_path = path.with_extension("lock");
+    for _ in 0..LOCK_ATTEMPTS {
+        match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+        {
+            Ok(_) => {
+                let result = f();
+                let _ = fs::remove_file(&lock_path);
+                return result;
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                let stale = fs::metadata(&lock_path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > STALE_LOCK_AGE);
+                if stale {
+                    let _ = fs::remove_file(&lock_path);
+                } else {
+                    thread::sleep(LOCK_RETRY_DELAY);
+                }
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Err(io::Error::new(
+        io::ErrorKind::WouldBlock,
+        "lease is locked by another client",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::{Command, Stdio};
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kp_sync_lease_{}_{}", name, instance_id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn acquire(dir: &Path, owner: &str, ttl: TimeDelta) -> LeaseStatus {
+        acquire_lease(dir, "Player.1234", owner, ttl, None, None).unwrap()
+    }
+
+    #[test]
+    fn first_client_acquires_lease() {
+        let dir = temp_dir("first");
+        assert_eq!(
+            acquire(&dir, "a", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+        assert_eq!(
+            acquire(&dir, "a", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+        assert!(matches!(
+            acquire(&dir, "b", TimeDelta::seconds(10)),
+            LeaseStatus::HeldBy(lease) if lease.owner == "a"
+        ));
+    }
+
+    #[test]
+    fn expired_lease_is_taken_over() {
+        let dir = temp_dir("expired");
+        assert_eq!(
+            acquire(&dir, "a", TimeDelta::seconds(-1)),
+            LeaseStatus::Acquired
+        );
+        assert_eq!(
+            acquire(&dir, "b", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+    }
+
+    #[test]
+    fn released_lease_is_free() {
+        let dir = temp_dir("released");
+        assert_eq!(
+            acquire(&dir, "a", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+        release_lease(&dir, "player.1234", "b").unwrap();
+        assert!(matches!(
+            acquire(&dir, "b", TimeDelta::seconds(10)),
+            LeaseStatus::HeldBy(_)
+        ));
+        release_lease(&dir, "player.1234", "a").unwrap();
+        assert_eq!(
+            acquire(&dir, "b", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+    }
+
+    #[test]
+    fn lease_shares_schedule() {
+        let dir = temp_dir("schedule");
+        let schedule = Some(ScheduledRefresh::OnNormalMapEnter);
+        acquire_lease(
+            &dir,
+            "Player.1234",
+            "a",
+            TimeDelta::seconds(10),
+            schedule.clone(),
+            None,
+        )
+        .unwrap();
+        match acquire(&dir, "b", TimeDelta::seconds(10)) {
+            LeaseStatus::HeldBy(lease) => assert_eq!(lease.scheduled_refresh, schedule),
+            status => panic!("unexpected status {:?}", status),
+        }
+    }
+
+    #[test]
+    fn stale_lock_is_removed() {
+        let dir = temp_dir("stale");
+        fs::create_dir_all(&dir).unwrap();
+        let lock = lease_path(&dir, "Player.1234").with_extension("lock");
+        fs::write(&lock, "").unwrap();
+        let old = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(&lock)
+            .unwrap()
+            .set_modified(old)
+            .unwrap();
+        assert_eq!(
+            acquire(&dir, "a", TimeDelta::seconds(10)),
+            LeaseStatus::Acquired
+        );
+    }
+
+    const EXIT_ACQUIRED: i32 = 10;
+    const EXIT_HELD: i32 = 11;
+
+    #[test]
+    fn only_one_process_acquires_lease() {
+        if let Ok(dir) = std::env::var("KP_SYNC_LEASE_TEST_DIR") {
+            let owner = std::process::id().to_string();
+            let status = acquire(Path::new(&dir), &owner, TimeDelta::seconds(30));
+            std::process::exit(match status {
+                LeaseStatus::Acquired => EXIT_ACQUIRED,
+                LeaseStatus::HeldBy(_) => EXIT_HELD,
+            });
+        }
+        let dir = temp_dir("processes");
+        let children: Vec<_> = (0..2)
+            .map(|_| {
+                Command::new(std::env::current_exe().unwrap())
+                    .args(["--exact", "lease::tests::only_one_process_acquires_lease"])
+                    .env("KP_SYNC_LEASE_TEST_DIR", &dir)
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        let mut exit_codes: Vec<_> = children
+            .into_iter()
+            .map(|mut child| child.wait().unwrap().code())
+            .collect();
+        exit_codes.sort();
+        assert_eq!(exit_codes, vec![Some(EXIT_ACQUIRED), Some(EXIT_HELD)]);
+    }
+}
@@ -1,8 +1,16 @@
 mod addon;
 mod api;
+mod combat;
 pub mod config;
 pub mod context;
+mod evtc;
+mod lease;
 mod render;
+mod schedule;
+mod session;
+mod state;
+mod storage;
+mod sync;
 mod thread;
 
 use crate::addon::Addon;
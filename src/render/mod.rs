@@ -1,5 +1,9 @@
+use crate::addon::Addon;
+use crate::config::default_date_format;
 use crate::context::scheduled_refresh::ScheduledRefresh;
-use chrono::TimeDelta;
+use crate::schedule::weekly_reset::next_weekly_reset;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, TimeDelta, Utc};
 use nexus::imgui::Ui;
 
 pub mod options;
@@ -30,12 +34,37 @@ fn table_rows<T: Renderable>(ui: &Ui, rows: Vec<T>) {
     }
 }
 
+impl Addon {
+    pub fn scheduled_refresh_status(&self) -> String {
+        match &self.context.other_client_lease {
+            Some(lease) => format!(
+                "handled by another client ({})",
+                scheduled_refresh_text(&lease.scheduled_refresh)
+            ),
+            None => scheduled_refresh_text(&self.context.scheduled_refresh),
+        }
+    }
+}
+
 pub fn scheduled_refresh_text(scheduled_refresh: &Option<ScheduledRefresh>) -> String {
     scheduled_refresh
         .as_ref()
         .map_or_else(|| "not planned".to_string(), |refresh| refresh.to_string())
 }
 
+pub fn valid_date_format(format: &str) -> bool {
+    !format.is_empty() && StrftimeItems::new(format).all(|item| item != Item::Error)
+}
+
+pub fn format_date(date: &DateTime<Utc>, format: &str) -> String {
+    let local_date = date.with_timezone(&Local);
+    if valid_date_format(format) {
+        local_date.format(format).to_string()
+    } else {
+        local_date.format(&default_date_format()).to_string()
+    }
+}
+
 pub fn countdown_str(delta: TimeDelta) -> String {
     if delta.num_minutes() > 0 {
         format!("{} minutes", delta.num_minutes() + 1)
@@ -48,3 +77,21 @@ pub fn countdown_str(delta: TimeDelta) -> String {
         }
     }
 }
+
+pub fn long_countdown_str(delta: TimeDelta) -> String {
+    let days = delta.num_days();
+    let hours = delta.num_hours() % 24;
+    let minutes = delta.num_minutes() % 60;
+    if days > 0 {
+        format!("{}d {}h {}m", days, hours, minutes)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes)
+    } else {
+        countdown_str(delta)
+    }
+}
+
+pub fn weekly_reset_text() -> String {
+    let now = Utc::now();
+    format!("in {}", long_countdown_str(next_weekly_reset(now) - now))
+}
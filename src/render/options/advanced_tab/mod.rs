@@ -1,7 +1,26 @@
+mod rules;
+
 use crate::addon::Addon;
-use crate::render::options::ERROR_COLOR;
+use crate::config::calendar::CalendarEvent;
+use crate::config::default_date_format;
+use crate::config::network::valid_proxy;
+use crate::config::presets::Preset;
+use crate::config::share::{export_settings_to_clipboard, import_settings_from_clipboard};
+use crate::render::options::{ERROR_COLOR, WARNING_COLOR};
+use crate::render::{format_date, valid_date_format};
+use chrono::{Local, NaiveTime, Timelike, Utc, Weekday};
 use nexus::imgui::{TreeNodeFlags, Ui};
 
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
 impl Addon {
     pub fn render_advanced_tab(&mut self, ui: &Ui) {
         self.render_notification_options(ui);
@@ -11,21 +30,42 @@ impl Addon {
         ) {
             ui.spacing();
             ui.checkbox(
-                "Enable scheduling on map load",
-                &mut self.config.scheduling_on_map_enter_enabled,
+                "Enable scheduling rules",
+                &mut self.config.scheduling_rules_enabled,
             );
+            if self.config.scheduling_rules_enabled {
+                ui.checkbox(
+                    "Refresh on map exit only after a detected boss kill (requires ArcDPS)",
+                    &mut self.config.refresh_only_on_kill,
+                );
+                self.render_character_scheduling(ui);
+            }
             ui.new_line();
-            if self.config.scheduling_on_map_enter_enabled {
-                ui.text("Maps that schedule refresh to be triggered when non-kp map is loaded: ");
+            if self.config.scheduling_rules_enabled {
+                ui.text("KP maps (schedule refresh to be triggered when non-kp map is loaded): ");
                 ui.spacing();
                 self.render_kp_maps(ui);
                 ui.new_line();
-                ui.text("Maps that extend scheduled refresh until non-kp map is loaded: ");
+                ui.text("Refresh retaining maps (extend scheduled refresh until non-kp map is loaded): ");
                 ui.spacing();
                 self.render_retain_refresh_maps(ui);
             }
             ui.new_line();
         }
+        if self.config.scheduling_rules_enabled {
+            self.render_rules(ui);
+        }
+        self.render_squad_trigger_options(ui);
+        self.render_evtc_watcher_options(ui);
+        self.render_weekly_reset_options(ui);
+        self.render_calendar_options(ui);
+        self.render_unload_refresh_options(ui);
+        self.render_display_options(ui);
+        self.render_preset_options(ui);
+        self.render_share_options(ui);
+        self.render_folder_sync_options(ui);
+        self.render_network_options(ui);
+        self.render_retry_options(ui);
         if ui.collapsing_header(
             "Additional information##kp",
             TreeNodeFlags::SPAN_AVAIL_WIDTH,
@@ -33,10 +73,68 @@ impl Addon {
             if let Some(m) = self.context.mumble {
                 ui.text(format!("Current map id: {}", m.read_map_id()));
             }
+            match &self.context.identity {
+                Some(identity) => ui.text(format!(
+                    "Current character: {} ({:?}, specialization id: {})",
+                    identity.name, identity.profession, identity.spec
+                )),
+                None => ui.text("Current character: unknown"),
+            }
+            if self.context.kills.is_empty() {
+                ui.text("Boss kills this session: none detected");
+            } else {
+                ui.text("Boss kills this session:");
+                for kill in &self.context.kills {
+                    ui.text(format!(
+                        "- {} ({})",
+                        kill.name,
+                        kill.date.with_timezone(&Local).format("%H:%M")
+                    ));
+                }
+            }
             ui.new_line();
         }
     }
 
+    fn render_character_scheduling(&mut self, ui: &Ui) {
+        if let Some(character) = self.current_character().cloned() {
+            let mut enabled = self.scheduling_enabled_for_character();
+            if ui.checkbox(
+                format!("Enable scheduling for current character ({})", character),
+                &mut enabled,
+            ) {
+                if enabled {
+                    self.config
+                        .scheduling_disabled_characters
+                        .retain(|disabled| disabled != &character);
+                } else {
+                    self.config.scheduling_disabled_characters.push(character);
+                }
+            }
+        }
+        let mut to_remove = None;
+        for (i, character) in self
+            .config
+            .scheduling_disabled_characters
+            .iter()
+            .enumerate()
+        {
+            ui.text_colored(ERROR_COLOR, "[X]");
+            ui.same_line_with_pos(-10f32);
+            if ui.invisible_button(format!("##sdc{}", i), [30f32, 30f32]) {
+                to_remove = Some(i);
+            }
+            if ui.is_item_hovered() {
+                ui.tooltip_text("Enable scheduling for this character");
+            }
+            ui.same_line_with_pos(24f32);
+            ui.text(format!("Scheduling disabled: {}", character));
+        }
+        if let Some(i) = to_remove {
+            self.config.scheduling_disabled_characters.remove(i);
+        }
+    }
+
     fn render_notification_options(&mut self, ui: &Ui) {
         if ui.collapsing_header(
             "Notifications##kp",
@@ -56,6 +154,396 @@ impl Addon {
                 "Notify on failed linked account refresh",
                 &mut self.config.notifications.notify_failure_linked,
             );
+            ui.checkbox(
+                "Notify with session summary",
+                &mut self.config.notifications.notify_session_summary,
+            );
+            ui.new_line();
+        }
+    }
+
+    fn render_squad_trigger_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header(
+            "Squad triggers (requires RealtimeAPI)##kp",
+            TreeNodeFlags::SPAN_AVAIL_WIDTH,
+        ) {
+            let triggers = &mut self.config.squad_triggers;
+            ui.checkbox(
+                "Refresh when squad is left or disbanded after visiting a KP map",
+                &mut triggers.refresh_on_squad_leave,
+            );
+            ui.checkbox(
+                "Refresh when squad size drops below threshold after visiting a KP map",
+                &mut triggers.refresh_on_squad_size_drop,
+            );
+            if triggers.refresh_on_squad_size_drop {
+                let mut threshold = triggers.squad_size_threshold as i32;
+                if ui.input_int("Squad size threshold", &mut threshold).build() {
+                    triggers.squad_size_threshold = threshold.max(1) as u32;
+                }
+            }
+            if let Some(rtapi) = &self.context.rtapi {
+                ui.text_disabled(format!("Current group size: {}", rtapi.group_member_count));
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_evtc_watcher_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("ArcDPS logs##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let watcher = &mut self.config.evtc_watcher;
+            ui.checkbox(
+                "Schedule refresh when a successful kill is found in ArcDPS logs",
+                &mut watcher.enabled,
+            );
+            if watcher.enabled {
+                ui.input_text("Log directory", &mut watcher.directory)
+                    .build();
+                ui.text_disabled(format!(
+                    "Logs processed this session: {}",
+                    self.context.evtc_watcher.processed.len()
+                ));
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_weekly_reset_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Weekly reset##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let weekly_reset = &mut self.config.weekly_reset;
+            ui.checkbox(
+                "Refresh shortly before weekly reset (Monday 07:30 UTC)",
+                &mut weekly_reset.refresh_before_reset,
+            );
+            if weekly_reset.refresh_before_reset {
+                let mut minutes = weekly_reset.minutes_before_reset as i32;
+                if ui.input_int("Minutes before reset", &mut minutes).build() {
+                    weekly_reset.minutes_before_reset = minutes.clamp(1, 24 * 60) as u32;
+                }
+            }
+            ui.checkbox(
+                "Refresh after the first KP map session of the week",
+                &mut weekly_reset.refresh_after_first_session,
+            );
+            ui.new_line();
+        }
+    }
+
+    fn render_unload_refresh_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Game exit##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let unload_refresh = &mut self.config.unload_refresh;
+            ui.checkbox(
+                "Attempt pending refresh when the game exits",
+                &mut unload_refresh.enabled,
+            );
+            if unload_refresh.enabled {
+                let mut time_budget_ms = unload_refresh.time_budget_ms as i32;
+                if ui
+                    .input_int("Time budget (ms)", &mut time_budget_ms)
+                    .build()
+                {
+                    unload_refresh.time_budget_ms = time_budget_ms.clamp(250, 5000) as u32;
+                }
+                ui.text_disabled(
+                    "If the refresh fails or times out, it runs on next game start instead",
+                );
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_preset_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Presets##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            ui.text_disabled("Presets override only the selected groups of settings.");
+            let active = self.active_preset_index();
+            let current = Preset::capture("", &self.config);
+            let mut to_remove = None;
+            let mut renamed_active = None;
+            for (i, preset) in self.config.presets.iter_mut().enumerate() {
+                if ui
+                    .input_text(format!("Name##preset{}", i), &mut preset.name)
+                    .build()
+                    && active == Some(i)
+                {
+                    renamed_active = Some(preset.name.clone());
+                }
+                let mut maps =
+                    preset.kp_map_ids.is_some() || preset.retain_refresh_map_ids.is_some();
+                if ui.checkbox(format!("Map lists##preset{}", i), &mut maps) {
+                    preset.kp_map_ids = current.kp_map_ids.clone().filter(|_| maps);
+                    preset.retain_refresh_map_ids =
+                        current.retain_refresh_map_ids.clone().filter(|_| maps);
+                }
+                ui.same_line();
+                let mut notifications = preset.notifications.is_some();
+                if ui.checkbox(format!("Notifications##preset{}", i), &mut notifications) {
+                    preset.notifications = current.notifications.clone().filter(|_| notifications);
+                }
+                ui.same_line();
+                let mut scheduling = preset.scheduling_rules_enabled.is_some()
+                    || preset.refresh_only_on_kill.is_some();
+                if ui.checkbox(format!("Scheduling flags##preset{}", i), &mut scheduling) {
+                    preset.scheduling_rules_enabled =
+                        current.scheduling_rules_enabled.filter(|_| scheduling);
+                    preset.refresh_only_on_kill =
+                        current.refresh_only_on_kill.filter(|_| scheduling);
+                }
+                if ui.button(format!("Update from current settings##preset{}", i)) {
+                    preset.update_from(&current);
+                }
+                ui.same_line();
+                if ui.button(format!("Remove preset##preset{}", i)) {
+                    to_remove = Some(i);
+                }
+                ui.separator();
+            }
+            if let Some(name) = renamed_active {
+                self.state.active_preset = Some(name);
+            }
+            if let Some(i) = to_remove {
+                if active == Some(i) {
+                    self.activate_preset(None);
+                }
+                self.config.presets.remove(i);
+            }
+            if ui.button("Add preset from current settings") {
+                let name = format!("Preset {}", self.config.presets.len() + 1);
+                let preset = Preset::capture(&name, &self.config);
+                self.config.presets.push(preset);
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_share_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Share settings##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            ui.text_disabled(
+                "Map lists, rules, schedules, presets and notifications. KP id is not included.",
+            );
+            if ui.button("Export settings") {
+                export_settings_to_clipboard();
+            }
+            ui.same_line();
+            if ui.button("Import settings") {
+                import_settings_from_clipboard();
+            }
+            let mut close = false;
+            match &self.context.ui.settings_import {
+                Some(Ok((settings, diff))) => {
+                    if diff.is_empty() {
+                        ui.text("Imported settings are the same as current settings.");
+                        close = ui.button("Close");
+                    } else {
+                        ui.text(format!("Settings from kp_sync v{}:", settings.version));
+                        for line in diff {
+                            ui.text(format!("- {}", line));
+                        }
+                        if ui.button("Apply") {
+                            settings.apply(&mut self.config, &mut self.state);
+                            close = true;
+                        }
+                        ui.same_line();
+                        if ui.button("Cancel") {
+                            close = true;
+                        }
+                    }
+                }
+                Some(Err(err)) => {
+                    ui.text_colored(ERROR_COLOR, format!("Import failed: {}", err));
+                    close = ui.button("Close");
+                }
+                None => {}
+            }
+            if close {
+                self.context.ui.settings_import = None;
+                self.validate_config();
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_folder_sync_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Sync folder##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let folder_sync = &mut self.config.folder_sync;
+            ui.checkbox(
+                "Sync settings and refresh history through a shared folder",
+                &mut folder_sync.enabled,
+            );
+            if folder_sync.enabled {
+                ui.input_text("Sync directory", &mut folder_sync.directory)
+                    .build();
+                ui.text_disabled(
+                    "Use a cloud synced folder, newer changes win when both machines changed a setting.",
+                );
+                match &self.state.sync.last_sync_date {
+                    Some(date) => ui.text(format!(
+                        "Last export: {}",
+                        format_date(date, &self.config.date_format)
+                    )),
+                    None => ui.text("Last export: never"),
+                }
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_network_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Network##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let network = &mut self.config.network;
+            let mut connect_timeout_sec = network.connect_timeout_sec as i32;
+            if ui
+                .input_int("Connect timeout (s)", &mut connect_timeout_sec)
+                .build()
+            {
+                network.connect_timeout_sec = connect_timeout_sec.clamp(1, 60) as u32;
+            }
+            let mut read_timeout_sec = network.read_timeout_sec as i32;
+            if ui
+                .input_int("Read timeout (s)", &mut read_timeout_sec)
+                .build()
+            {
+                network.read_timeout_sec = read_timeout_sec.clamp(1, 300) as u32;
+            }
+            ui.input_text("Proxy", &mut network.proxy).build();
+            if !network.proxy.is_empty() && !valid_proxy(&network.proxy) {
+                ui.text_colored(ERROR_COLOR, "Invalid proxy, requests are sent without it");
+            }
+            ui.text_disabled("Leave empty for a direct connection, e.g. socks5://127.0.0.1:1080");
+            ui.checkbox("Strict TLS verification", &mut network.strict_tls);
+            if !network.strict_tls {
+                ui.text_colored(
+                    WARNING_COLOR,
+                    "Server hostnames are not verified against their certificates",
+                );
+            }
+            ui.text_disabled("Changes apply within a few seconds");
+            ui.new_line();
+        }
+    }
+
+    fn render_retry_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Retries##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let retry = &mut self.config.retry;
+            ui.checkbox(
+                "Retry refresh after network errors and kp.me outages",
+                &mut retry.enabled,
+            );
+            if retry.enabled {
+                let mut max_attempts = retry.max_attempts as i32;
+                if ui.input_int("Max attempts", &mut max_attempts).build() {
+                    retry.max_attempts = max_attempts.clamp(2, 10) as u32;
+                }
+                let mut base_delay_sec = retry.base_delay_sec as i32;
+                if ui
+                    .input_int("First retry delay (s)", &mut base_delay_sec)
+                    .build()
+                {
+                    retry.base_delay_sec = base_delay_sec.clamp(5, 600) as u32;
+                }
+                let mut max_total_delay_min = (retry.max_total_delay_sec / 60) as i32;
+                if ui
+                    .input_int("Give up after (min)", &mut max_total_delay_min)
+                    .build()
+                {
+                    retry.max_total_delay_sec = max_total_delay_min.clamp(1, 24 * 60) as u32 * 60;
+                }
+                ui.text_disabled(
+                    "The delay doubles after every failed attempt and is slightly randomized",
+                );
+            }
+            ui.new_line();
+        }
+    }
+
+    fn render_display_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Display##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            ui.input_text("Date format", &mut self.config.date_format)
+                .build();
+            ui.same_line();
+            if ui.button("Reset##date_format") {
+                self.config.date_format = default_date_format();
+            }
+            if valid_date_format(&self.config.date_format) {
+                ui.text(format!(
+                    "Preview: {}",
+                    format_date(&Utc::now(), &self.config.date_format)
+                ));
+            } else {
+                ui.text_colored(
+                    ERROR_COLOR,
+                    "Invalid date format, default format is used instead",
+                );
+            }
+            ui.text_disabled("Uses strftime syntax, e.g. %d.%m.%Y %H:%M");
+            ui.new_line();
+        }
+    }
+
+    fn render_calendar_options(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Raid calendar##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            let calendar = &mut self.config.calendar;
+            ui.checkbox(
+                "Refresh before and after calendar events",
+                &mut calendar.enabled,
+            );
+            if calendar.enabled {
+                let mut minutes_before = calendar.refresh_minutes_before as i32;
+                if ui
+                    .input_int("Minutes before start", &mut minutes_before)
+                    .build()
+                {
+                    calendar.refresh_minutes_before = minutes_before.clamp(0, 24 * 60) as u32;
+                }
+                let mut minutes_after = calendar.refresh_minutes_after as i32;
+                if ui
+                    .input_int("Minutes after end", &mut minutes_after)
+                    .build()
+                {
+                    calendar.refresh_minutes_after = minutes_after.clamp(0, 24 * 60) as u32;
+                }
+                ui.spacing();
+                let mut to_remove = None;
+                for (i, event) in calendar.events.iter_mut().enumerate() {
+                    ui.input_text(format!("Name##cal{}", i), &mut event.name)
+                        .build();
+                    for day in WEEKDAYS {
+                        let mut checked = event.days.contains(&day);
+                        if ui.checkbox(format!("{}##cal{}{}", day, i, day), &mut checked) {
+                            if checked {
+                                event.days.push(day);
+                            } else {
+                                event.days.retain(|event_day| *event_day != day);
+                            }
+                        }
+                        ui.same_line();
+                    }
+                    ui.new_line();
+                    render_time_input(
+                        ui,
+                        "Start time (hour, minute)",
+                        &format!("cal{}", i),
+                        &mut event.start_time,
+                    );
+                    let mut duration = event.duration_minutes as i32;
+                    ui.set_next_item_width(100f32);
+                    if ui
+                        .input_int(format!("Duration (minutes)##cal{}", i), &mut duration)
+                        .build()
+                    {
+                        event.duration_minutes = duration.clamp(1, 24 * 60) as u32;
+                    }
+                    if ui.button(format!("Remove event##cal{}", i)) {
+                        to_remove = Some(i);
+                    }
+                    ui.separator();
+                }
+                if let Some(i) = to_remove {
+                    calendar.events.remove(i);
+                }
+                if ui.button("Add event##cal") {
+                    calendar.events.push(CalendarEvent::default());
+                }
+            }
             ui.new_line();
         }
     }
@@ -188,6 +676,25 @@ impl Addon {
     }
 }
 
+fn render_time_input(ui: &Ui, label: &str, id: &str, time: &mut NaiveTime) {
+    let mut hour = time.hour() as i32;
+    let mut minute = time.minute() as i32;
+    ui.set_next_item_width(100f32);
+    let hour_changed = ui.input_int(format!("##{}h", id), &mut hour).build();
+    ui.same_line();
+    ui.set_next_item_width(100f32);
+    let minute_changed = ui
+        .input_int(format!("{}##{}m", label, id), &mut minute)
+        .build();
+    if hour_changed || minute_changed {
+        if let Some(new_time) =
+            NaiveTime::from_hms_opt(hour.clamp(0, 23) as u32, minute.clamp(0, 59) as u32, 0)
+        {
+            *time = new_time;
+        }
+    }
+}
+
 enum SearchMapType {
     KpMap,
     RetainMap,
@@ -0,0 +1,150 @@
+use super::render_time_input;
+use crate::addon::Addon;
+use crate::config::rules::{default_rules, Action, Condition, MapCategory, Rule};
+use crate::context::ui::UiContext;
+use crate::render::options::ERROR_COLOR;
+use nexus::imgui::{TreeNodeFlags, Ui};
+
+impl Addon {
+    pub(super) fn render_rules(&mut self, ui: &Ui) {
+        if ui.collapsing_header("Scheduling rules##kp", TreeNodeFlags::SPAN_AVAIL_WIDTH) {
+            ui.text_disabled(
+                "Rules are evaluated every second, all conditions of a rule have to match.",
+            );
+            ui.spacing();
+            let mut to_remove = None;
+            for (i, rule) in self.config.rules.iter_mut().enumerate() {
+                if render_rule(ui, i, rule, &mut self.context.ui) {
+                    to_remove = Some(i);
+                }
+                ui.separator();
+            }
+            if let Some(i) = to_remove {
+                self.config.rules.remove(i);
+                self.context.matching_rules.clear();
+            }
+            if ui.button("Add rule##rules") {
+                self.config.rules.push(Rule::default());
+            }
+            ui.same_line();
+            if ui.button("Restore default rules##rules") {
+                self.config.rules = default_rules();
+                self.context.matching_rules.clear();
+            }
+            ui.new_line();
+        }
+    }
+}
+
+fn render_rule(ui: &Ui, i: usize, rule: &mut Rule, ui_context: &mut UiContext) -> bool {
+    ui.checkbox(format!("##rule_enabled{}", i), &mut rule.enabled);
+    ui.same_line();
+    ui.input_text(format!("##rule_name{}", i), &mut rule.name)
+        .build();
+    if rule.conditions.is_empty() {
+        ui.text_colored(ERROR_COLOR, "Rule without conditions never matches.");
+    }
+    let mut condition_to_remove = None;
+    for (j, condition) in rule.conditions.iter_mut().enumerate() {
+        ui.text_colored(ERROR_COLOR, "[X]");
+        ui.same_line_with_pos(-10f32);
+        if ui.invisible_button(format!("##rule_condition{}_{}", i, j), [30f32, 30f32]) {
+            condition_to_remove = Some(j);
+        }
+        ui.same_line_with_pos(24f32);
+        render_condition(ui, &format!("{}_{}", i, j), condition, ui_context);
+    }
+    if let Some(j) = condition_to_remove {
+        rule.conditions.remove(j);
+    }
+    ui.set_next_item_width(200f32);
+    ui.combo_simple_string(
+        format!("##rule_new_condition{}", i),
+        &mut ui_context.new_condition_index,
+        &Condition::LABELS,
+    );
+    ui.same_line();
+    if ui.button(format!("Add condition##rule{}", i)) {
+        rule.conditions
+            .push(Condition::from_index(ui_context.new_condition_index));
+    }
+
+    let mut action_index = rule.action.index();
+    ui.set_next_item_width(200f32);
+    if ui.combo_simple_string(
+        format!("Action##rule{}", i),
+        &mut action_index,
+        &Action::LABELS,
+    ) {
+        rule.action = Action::from_index(action_index);
+    }
+    if let Action::ScheduleIn { minutes } = &mut rule.action {
+        let mut value = *minutes as i32;
+        ui.set_next_item_width(100f32);
+        if ui
+            .input_int(format!("Minutes##rule{}", i), &mut value)
+            .build()
+        {
+            *minutes = value.clamp(0, 24 * 60) as u32;
+        }
+    }
+    ui.button(format!("Remove rule##rule{}", i))
+}
+
+fn render_condition(ui: &Ui, id: &str, condition: &mut Condition, ui_context: &mut UiContext) {
+    let label = condition.label();
+    match condition {
+        Condition::MapIn { map_ids } => {
+            ui.text(format!("{}: {:?}", label, map_ids));
+            ui.set_next_item_width(100f32);
+            ui.input_int(format!("##map_id{}", id), &mut ui_context.new_rule_map_id)
+                .build();
+            ui.same_line();
+            if ui.button(format!("Add map##{}", id)) && ui_context.new_rule_map_id > 0 {
+                let map_id = ui_context.new_rule_map_id as u32;
+                if !map_ids.contains(&map_id) {
+                    map_ids.push(map_id);
+                }
+            }
+            ui.same_line();
+            if ui.button(format!("Clear maps##{}", id)) {
+                map_ids.clear();
+            }
+        }
+        Condition::MapCategory { category } => {
+            let mut index = category.index();
+            ui.set_next_item_width(200f32);
+            if ui.combo_simple_string(
+                format!("{}##{}", label, id),
+                &mut index,
+                &MapCategory::LABELS,
+            ) {
+                *category = MapCategory::from_index(index);
+            }
+        }
+        Condition::TimeWindow { start, end } => {
+            ui.text(label);
+            render_time_input(ui, "From (hour, minute)", &format!("from{}", id), start);
+            render_time_input(ui, "To (hour, minute)", &format!("to{}", id), end);
+        }
+        Condition::Account { name } => {
+            ui.input_text(format!("{}##{}", label, id), name).build();
+        }
+        Condition::Character { name } => {
+            ui.input_text(format!("{}##{}", label, id), name).build();
+        }
+        Condition::SessionLongerThan { minutes } => {
+            let mut value = *minutes as i32;
+            ui.set_next_item_width(100f32);
+            if ui
+                .input_int(format!("{} (minutes)##{}", label, id), &mut value)
+                .build()
+            {
+                *minutes = value.clamp(0, 24 * 60) as u32;
+            }
+        }
+        Condition::InCombat { in_combat } => {
+            ui.checkbox(format!("In combat (requires ArcDPS)##{}", id), in_combat);
+        }
+    }
+}
@@ -3,7 +3,7 @@ use crate::api::kp::kp_response::KpResponse;
 use crate::api::kp::linked_ids::fetch_linked_ids_thread;
 use crate::api::kp::refresh::refresh_kp_thread;
 use crate::render::options::ERROR_COLOR;
-use crate::render::{scheduled_refresh_text, table_rows};
+use crate::render::{format_date, table_rows, weekly_reset_text};
 use nexus::imgui::{TreeNodeFlags, Ui};
 
 impl Addon {
@@ -48,7 +48,7 @@ impl Addon {
                 if context_valid && ui.button("Refresh") {
                     refresh_kp_thread();
                 }
-            } else if let KpResponse::InvalidId(invalid_id) = &self.context.main_kp_response {
+            } else if let KpResponse::InvalidId(invalid_id, _) = &self.context.main_kp_response {
                 if invalid_id.eq(&self.config.kp_identifiers.main_id) {
                     ui.text_colored(ERROR_COLOR, "KP Id not found. Enter different value.");
                 }
@@ -78,25 +78,25 @@ impl Addon {
     }
 
     fn on_kp_id_change(&mut self) {
-        self.config.kp_identifiers.linked_ids = None;
-        self.config.last_refresh_date = None;
+        self.state.linked_ids = None;
+        self.state.last_refresh_date = None;
         self.context.ui.errors.linked_ids = false;
         self.context.scheduled_refresh = None;
         self.context.linked_kp_responses.clear();
     }
 
     fn render_linked_accounts(&mut self, ui: &Ui) {
-        let mut checkbox_checked = self.config.kp_identifiers.linked_ids.is_some();
+        let mut checkbox_checked = self.state.linked_ids.is_some();
         ui.checkbox("Refresh linked accounts", &mut checkbox_checked);
 
         if checkbox_checked {
-            if self.config.kp_identifiers.linked_ids.is_none() {
+            if self.state.linked_ids.is_none() {
                 self.context.ui.errors.linked_ids = false;
-                self.config.kp_identifiers.linked_ids = Some(Vec::new());
+                self.state.linked_ids = Some(Vec::new());
                 fetch_linked_ids_thread();
             }
         } else {
-            self.config.kp_identifiers.linked_ids = None;
+            self.state.linked_ids = None;
             self.context.linked_kp_responses.clear();
         }
         self.render_linked_ids(ui);
@@ -109,10 +109,13 @@ impl Addon {
                 ui,
                 vec![
                     ("Current status".to_string(), self.current_status_text()),
+                    ("Last response".to_string(), self.last_response_text()),
                     (
                         "Scheduled refresh".to_string(),
-                        scheduled_refresh_text(&self.context.scheduled_refresh),
+                        self.scheduled_refresh_status(),
                     ),
+                    ("Weekly reset".to_string(), weekly_reset_text()),
+                    ("Active preset".to_string(), self.active_preset_text()),
                     (
                         "Last successful refresh".to_string(),
                         self.last_refresh_text(),
@@ -125,6 +128,8 @@ impl Addon {
     fn current_status_text(&mut self) -> String {
         if self.context.refresh_in_progress {
             "refresh is in progress..".to_string()
+        } else if self.context.other_client_lease.is_some() {
+            KpResponse::HandledByOtherClient.to_string()
         } else if self.config.valid() {
             self.context.main_kp_response.to_string()
         } else {
@@ -132,9 +137,20 @@ impl Addon {
         }
     }
 
+    fn last_response_text(&self) -> String {
+        match self.context.main_kp_response.meta() {
+            Some(meta) => format!(
+                "{} ({})",
+                meta,
+                format_date(&meta.date, &self.config.date_format)
+            ),
+            None => "unavailable".to_string(),
+        }
+    }
+
     fn last_refresh_text(&mut self) -> String {
-        match self.config.last_refresh_date {
-            Some(last_refresh) => last_refresh.format("%Y-%m-%d %H:%M").to_string(),
+        match self.state.last_refresh_date {
+            Some(last_refresh) => format_date(&last_refresh, &self.config.date_format),
             None => "unavailable".to_string(),
         }
     }
@@ -142,7 +158,7 @@ impl Addon {
     fn render_linked_ids(&mut self, ui: &Ui) {
         if self.context.ui.errors.linked_ids {
             ui.text_colored(ERROR_COLOR, "Linked accounts not found");
-        } else if let Some(ids) = &self.config.kp_identifiers.linked_ids {
+        } else if let Some(ids) = &self.state.linked_ids {
             if ids.is_empty() {
                 ui.text("Loading..");
             } else {
@@ -153,7 +169,7 @@ impl Addon {
                 ui.spacing();
                 if ui.button("Update account list") {
                     self.context.ui.errors.linked_ids = false;
-                    self.config.kp_identifiers.linked_ids = Some(Vec::new());
+                    self.state.linked_ids = Some(Vec::new());
                     fetch_linked_ids_thread();
                 }
             }
@@ -165,7 +181,7 @@ impl Addon {
                     let kp_id = &response.0;
                     let kp_response = &response.1;
                     match kp_response {
-                        KpResponse::Success => {}
+                        KpResponse::Success(_) => {}
                         _ => ui.text_colored(
                             ERROR_COLOR,
                             format!("Could not refresh {}: {}", kp_id, kp_response),
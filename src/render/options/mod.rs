@@ -1,11 +1,16 @@
 mod advanced_tab;
 mod general_tab;
+mod sessions_tab;
 use crate::addon::Addon;
-use nexus::imgui::Ui;
+use crate::config::validation::Severity;
+use nexus::imgui::{TreeNodeFlags, Ui};
 
 const ERROR_COLOR: [f32; 4] = [0.4, 0.4, 0.4, 1.0];
+const WARNING_COLOR: [f32; 4] = [0.9, 0.7, 0.2, 1.0];
+const SEVERE_COLOR: [f32; 4] = [0.9, 0.3, 0.3, 1.0];
 impl Addon {
     pub fn render_options(&mut self, ui: &Ui) {
+        self.render_config_issues(ui);
         if let Some(_token) = ui.tab_bar("options") {
             if let Some(_token) = ui.tab_item("General") {
                 self.render_general_tab(ui);
@@ -14,6 +19,42 @@ impl Addon {
             if let Some(_token) = ui.tab_item("Advanced") {
                 self.render_advanced_tab(ui);
             }
+
+            if let Some(_token) = ui.tab_item("Sessions") {
+                self.render_sessions_tab(ui);
+            }
+        }
+    }
+
+    fn render_config_issues(&mut self, ui: &Ui) {
+        if self.context.config_issues.is_empty() {
+            return;
+        }
+        if ui.collapsing_header(
+            format!(
+                "Configuration issues ({})##kp",
+                self.context.config_issues.len()
+            ),
+            TreeNodeFlags::SPAN_AVAIL_WIDTH | TreeNodeFlags::DEFAULT_OPEN,
+        ) {
+            let mut to_fix = None;
+            for (i, issue) in self.context.config_issues.iter().enumerate() {
+                let color = match issue.severity() {
+                    Severity::Error => SEVERE_COLOR,
+                    Severity::Warning => WARNING_COLOR,
+                };
+                ui.text_colored(color, issue.to_string());
+                if let Some(label) = issue.fix_label() {
+                    ui.same_line();
+                    if ui.small_button(format!("{}##issue{}", label, i)) {
+                        to_fix = Some(i);
+                    }
+                }
+            }
+            if let Some(i) = to_fix {
+                self.fix_config_issue(i);
+            }
+            ui.new_line();
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::addon::Addon;
+use crate::render::{format_date, table_rows};
+use crate::session::duration_text;
+use chrono::Local;
+use nexus::imgui::{TreeNodeFlags, Ui};
+
+impl Addon {
+    pub fn render_sessions_tab(&mut self, ui: &Ui) {
+        if ui.collapsing_header(
+            "Current session##kp",
+            TreeNodeFlags::SPAN_AVAIL_WIDTH | TreeNodeFlags::DEFAULT_OPEN,
+        ) {
+            match &self.context.session {
+                Some(session) => {
+                    ui.text(format!(
+                        "Started at {}, lasting {}",
+                        session.start_date.with_timezone(&Local).format("%H:%M"),
+                        duration_text(session.duration())
+                    ));
+                    ui.text(format!(
+                        "Maps visited: {}, refresh attempts: {}",
+                        session.map_visits.len(),
+                        session.refresh_attempts
+                    ));
+                }
+                None => ui.text_disabled("Session starts when a KP map is entered."),
+            }
+            ui.new_line();
+        }
+        if ui.collapsing_header(
+            "Recent sessions##kp",
+            TreeNodeFlags::SPAN_AVAIL_WIDTH | TreeNodeFlags::DEFAULT_OPEN,
+        ) {
+            if self.context.sessions.is_empty() {
+                ui.text_disabled("No sessions recorded yet.");
+            } else if let Some(_t) = ui.begin_table("sessions", 2) {
+                ui.table_next_row();
+                table_rows(
+                    ui,
+                    self.context
+                        .sessions
+                        .iter()
+                        .map(|session| {
+                            (
+                                format_date(&session.start_date, &self.config.date_format),
+                                session.summary.clone(),
+                            )
+                        })
+                        .collect(),
+                );
+            }
+            ui.new_line();
+        }
+    }
+}
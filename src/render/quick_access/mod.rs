@@ -1,6 +1,9 @@
 use crate::addon::Addon;
 use crate::api::kp::refresh::refresh_kp_thread;
-use crate::render::scheduled_refresh_text;
+use crate::state::scheduling_pause::SchedulingPause;
+use crate::render::{countdown_str, long_countdown_str};
+use crate::schedule::calendar::{next_event, next_refresh_date};
+use chrono::{Local, TimeDelta, Utc};
 use nexus::imgui::Ui;
 use crate::thread::copy_kp_id_to_clipboard;
 
@@ -8,8 +11,15 @@ impl Addon {
     pub fn render_quick_access(&mut self, ui: &Ui) {
         ui.text(format!(
             "Scheduled refresh: {}",
-            scheduled_refresh_text(&self.context.scheduled_refresh)
+            self.scheduled_refresh_status()
         ));
+        if self.config.calendar.enabled {
+            self.render_calendar_info(ui);
+        }
+        ui.spacing();
+        self.render_pause_options(ui);
+        ui.spacing();
+        self.render_presets(ui);
         ui.spacing();
         if self.config.valid() && self.context.valid(&self.config.kp_identifiers.main_id) {
             if ui.button(" Refresh ") {
@@ -23,4 +33,86 @@ impl Addon {
             ui.text_disabled("Refresh (configuration is not valid)");
         }
     }
+
+    fn render_presets(&mut self, ui: &Ui) {
+        if self.config.presets.is_empty() {
+            return;
+        }
+        ui.text(format!("Preset: {}", self.active_preset_text()));
+        let active = self.active_preset_index();
+        let mut selected = None;
+        for (i, preset) in self.config.presets.iter().enumerate() {
+            if i > 0 {
+                ui.same_line();
+            }
+            if ui.radio_button_bool(format!("{}##preset{}", preset.name, i), active == Some(i)) {
+                selected = Some(Some(i));
+            }
+        }
+        if active.is_some() {
+            ui.same_line();
+            if ui.button(" Clear ") {
+                selected = Some(None);
+            }
+        }
+        if let Some(index) = selected {
+            self.activate_preset(index);
+        }
+    }
+
+    fn render_pause_options(&mut self, ui: &Ui) {
+        if self.scheduling_paused() {
+            if let Some(pause) = &self.state.scheduling_pause {
+                ui.text(format!("Scheduling: {}", pause));
+            }
+            if ui.button(" Resume ") {
+                self.resume_scheduling();
+            }
+            return;
+        }
+        ui.text("Pause scheduling:");
+        if ui.button(" 1h ") {
+            self.pause_scheduling(SchedulingPause::Until {
+                date: Utc::now() + TimeDelta::hours(1),
+            });
+        }
+        ui.same_line();
+        if ui.button(" 4h ") {
+            self.pause_scheduling(SchedulingPause::Until {
+                date: Utc::now() + TimeDelta::hours(4),
+            });
+        }
+        ui.same_line();
+        if ui.button(" Until next game start ") {
+            self.pause_scheduling(SchedulingPause::NextGameStart);
+        }
+        ui.same_line();
+        if ui.button(" Until resumed ") {
+            self.pause_scheduling(SchedulingPause::UntilResumed);
+        }
+    }
+
+    fn render_calendar_info(&self, ui: &Ui) {
+        let now = Local::now();
+        match next_event(&self.config.calendar, now) {
+            Some(occurrence) if occurrence.start <= now => ui.text(format!(
+                "Calendar: {} in progress, ends in {}",
+                occurrence.event.name,
+                countdown_str(occurrence.end() - now)
+            )),
+            Some(occurrence) => ui.text(format!(
+                "Next calendar event: {} ({}, in {})",
+                occurrence.event.name,
+                occurrence.start.format("%a %H:%M"),
+                long_countdown_str(occurrence.start - now)
+            )),
+            None => ui.text_disabled("Next calendar event: none"),
+        }
+        if let Some(date) = next_refresh_date(&self.config.calendar, now) {
+            ui.text(format!(
+                "Next calendar refresh: {}",
+                date.format("%a %H:%M")
+            ));
+        }
+    }
 }
@@ -0,0 +1,198 @@
+use crate::config::calendar::{Calendar, CalendarEvent};
+use chrono::{DateTime, Datelike, Local, TimeDelta, TimeZone};
+
+const LOOKBACK_DAYS: i64 = 1;
+const LOOKAHEAD_DAYS: i64 = 8;
+
+pub struct EventOccurrence<'a, Tz: TimeZone = Local> {
+    pub event: &'a CalendarEvent,
+    pub start: DateTime<Tz>,
+}
+
+impl<Tz: TimeZone> EventOccurrence<'_, Tz> {
+    pub fn end(&self) -> DateTime<Tz> {
+        self.start.clone() + TimeDelta::minutes(self.event.duration_minutes as i64)
+    }
+}
+
+fn occurrences<'a, Tz: TimeZone>(
+    calendar: &'a Calendar,
+    now: &DateTime<Tz>,
+) -> Vec<EventOccurrence<'a, Tz>> {
+    let today = now.date_naive();
+    let mut occurrences = Vec::new();
+    for offset in -LOOKBACK_DAYS..=LOOKAHEAD_DAYS {
+        let date = today + TimeDelta::days(offset);
+        for event in calendar
+            .events
+            .iter()
+            .filter(|event| event.days.contains(&date.weekday()))
+        {
+            if let Some(start) = now
+                .timezone()
+                .from_local_datetime(&date.and_time(event.start_time))
+                .earliest()
+            {
+                occurrences.push(EventOccurrence { event, start });
+            }
+        }
+    }
+    occurrences
+}
+
+pub fn next_refresh_date<Tz: TimeZone>(
+    calendar: &Calendar,
+    after: DateTime<Tz>,
+) -> Option<DateTime<Tz>> {
+    let before = TimeDelta::minutes(calendar.refresh_minutes_before as i64);
+    let past_end = TimeDelta::minutes(calendar.refresh_minutes_after as i64);
+    occurrences(calendar, &after)
+        .iter()
+        .flat_map(|occurrence| {
+            [
+                occurrence.start.clone() - before,
+                occurrence.end() + past_end,
+            ]
+        })
+        .filter(|date| *date > after)
+        .min()
+}
+
+pub fn next_event<Tz: TimeZone>(
+    calendar: &Calendar,
+    now: DateTime<Tz>,
+) -> Option<EventOccurrence<'_, Tz>> {
+    occurrences(calendar, &now)
+        .into_iter()
+        .filter(|occurrence| occurrence.end() > now)
+        .min_by_key(|occurrence| occurrence.start.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+
+    // Central European time with the 2024 switch to summer time, 02:00 to 03:00 on March 31st
+    #[derive(Debug, Clone, Copy)]
+    struct Cet;
+
+    const WINTER: i32 = 3600;
+    const SUMMER: i32 = 7200;
+
+    fn switch() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 31)
+            .unwrap()
+            .and_hms_opt(1, 0, 0)
+            .unwrap()
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let gap_start = switch() + TimeDelta::seconds(WINTER as i64);
+            let gap_end = switch() + TimeDelta::seconds(SUMMER as i64);
+            if *local < gap_start {
+                LocalResult::Single(FixedOffset::east_opt(WINTER).unwrap())
+            } else if *local < gap_end {
+                LocalResult::None
+            } else {
+                LocalResult::Single(FixedOffset::east_opt(SUMMER).unwrap())
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let offset = if *utc < switch() { WINTER } else { SUMMER };
+            FixedOffset::east_opt(offset).unwrap()
+        }
+    }
+
+    fn utc(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap()
+    }
+
+    fn calendar(days: Vec<Weekday>, hour: u32, duration_minutes: u32) -> Calendar {
+        Calendar {
+            enabled: true,
+            refresh_minutes_before: 5,
+            refresh_minutes_after: 10,
+            events: vec![CalendarEvent {
+                name: "Raid night".to_string(),
+                days,
+                start_time: NaiveTime::from_hms_opt(hour, 0, 0).unwrap(),
+                duration_minutes,
+            }],
+        }
+    }
+
+    #[test]
+    fn refreshes_before_and_after_event() {
+        // 2024-05-07 is a Tuesday
+        let calendar = calendar(vec![Weekday::Tue], 20, 180);
+        assert_eq!(
+            next_refresh_date(&calendar, utc(7, 12, 0)),
+            Some(utc(7, 19, 55))
+        );
+        assert_eq!(
+            next_refresh_date(&calendar, utc(7, 19, 55)),
+            Some(utc(7, 23, 10))
+        );
+        assert_eq!(
+            next_refresh_date(&calendar, utc(7, 23, 10)),
+            Some(utc(14, 19, 55))
+        );
+    }
+
+    #[test]
+    fn event_spanning_midnight() {
+        let calendar = calendar(vec![Weekday::Tue], 23, 120);
+        let now = utc(8, 0, 30);
+        assert_eq!(next_refresh_date(&calendar, now), Some(utc(8, 1, 10)));
+        let event = next_event(&calendar, now).unwrap();
+        assert_eq!(event.start, utc(7, 23, 0));
+        assert_eq!(event.end(), utc(8, 1, 0));
+    }
+
+    #[test]
+    fn event_without_days_never_occurs() {
+        let calendar = calendar(vec![], 20, 180);
+        assert_eq!(next_refresh_date(&calendar, utc(7, 12, 0)), None);
+        assert!(next_event(&calendar, utc(7, 12, 0)).is_none());
+    }
+
+    #[test]
+    fn event_in_dst_gap_is_skipped() {
+        // 2024-03-31 is a Sunday, 02:30 does not exist on that day
+        let calendar = calendar(vec![Weekday::Sun], 2, 60);
+        let now = Cet.with_ymd_and_hms(2024, 3, 30, 12, 0, 0).unwrap();
+        let event = next_event(&calendar, now).unwrap();
+        assert_eq!(
+            event.start,
+            Cet.with_ymd_and_hms(2024, 4, 7, 2, 0, 0).unwrap()
+        );
+        let calendar = Calendar {
+            events: vec![CalendarEvent {
+                start_time: NaiveTime::from_hms_opt(2, 30, 0).unwrap(),
+                ..calendar.events[0].clone()
+            }],
+            ..calendar
+        };
+        assert_eq!(
+            next_refresh_date(&calendar, now),
+            Some(Cet.with_ymd_and_hms(2024, 4, 7, 2, 25, 0).unwrap())
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod calendar;
+pub mod rules;
+pub mod weekly_reset;
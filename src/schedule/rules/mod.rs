@@ -0,0 +1,232 @@
+use crate::config::rules::{Action, Condition, MapCategory, Rule};
+use crate::context::scheduled_refresh::ScheduledRefresh;
+use chrono::{DateTime, NaiveTime, TimeDelta, Utc};
+
+pub struct RuleInput {
+    pub map_id: u32,
+    pub map_category: MapCategory,
+    pub time: NaiveTime,
+    pub account: String,
+    pub character: Option<String>,
+    pub session_minutes: Option<i64>,
+    pub in_combat: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct RuleOutcome {
+    pub refresh_now: bool,
+    pub schedule_in_minutes: Option<u32>,
+    pub schedule_on_release: bool,
+    pub held: bool,
+    pub suppressed: bool,
+}
+
+impl RuleOutcome {
+    pub fn scheduled_refresh(
+        &self,
+        current: Option<ScheduledRefresh>,
+        now: DateTime<Utc>,
+    ) -> Option<ScheduledRefresh> {
+        let scheduled_refresh = if self.refresh_now {
+            Some(ScheduledRefresh::OnTime(now))
+        } else if let Some(minutes) = self.schedule_in_minutes {
+            Some(ScheduledRefresh::OnTime(
+                now + TimeDelta::minutes(minutes as i64),
+            ))
+        } else if self.schedule_on_release {
+            Some(ScheduledRefresh::OnNormalMapEnter)
+        } else {
+            current
+        };
+        scheduled_refresh.filter(|_| !self.suppressed)
+    }
+}
+
+pub fn released(scheduled_refresh: &Option<ScheduledRefresh>, held: bool) -> bool {
+    !held && *scheduled_refresh == Some(ScheduledRefresh::OnNormalMapEnter)
+}
+
+impl Condition {
+    pub fn matches(&self, input: &RuleInput) -> bool {
+        match self {
+            Condition::MapIn { map_ids } => map_ids.contains(&input.map_id),
+            Condition::MapCategory { category } => *category == input.map_category,
+            Condition::TimeWindow { start, end } => {
+                if start <= end {
+                    *start <= input.time && input.time < *end
+                } else {
+                    input.time >= *start || input.time < *end
+                }
+            }
+            Condition::Account { name } => input.account.eq_ignore_ascii_case(name),
+            Condition::Character { name } => input
+                .character
+                .as_ref()
+                .is_some_and(|character| character.eq_ignore_ascii_case(name)),
+            Condition::SessionLongerThan { minutes } => input
+                .session_minutes
+                .is_some_and(|session_minutes| session_minutes >= *minutes as i64),
+            Condition::InCombat { in_combat } => *in_combat == input.in_combat,
+        }
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, input: &RuleInput) -> bool {
+        self.enabled
+            && !self.conditions.is_empty()
+            && self
+                .conditions
+                .iter()
+                .all(|condition| condition.matches(input))
+    }
+}
+
+pub fn matching_rules(rules: &[Rule], input: &RuleInput) -> Vec<usize> {
+    rules
+        .iter()
+        .enumerate()
+        .filter(|(_, rule)| rule.matches(input))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+pub fn evaluate(rules: &[Rule], matching: &[usize], previously_matching: &[usize]) -> RuleOutcome {
+    let mut outcome = RuleOutcome::default();
+    for &i in matching {
+        let started = !previously_matching.contains(&i);
+        match &rules[i].action {
+            Action::RefreshNow => outcome.refresh_now |= started,
+            Action::ScheduleIn { minutes } => {
+                if started {
+                    outcome.schedule_in_minutes = Some(
+                        outcome
+                            .schedule_in_minutes
+                            .map_or(*minutes, |current| current.min(*minutes)),
+                    );
+                }
+            }
+            Action::ScheduleOnRelease => {
+                outcome.held = true;
+                outcome.schedule_on_release |= started;
+            }
+            Action::Hold => outcome.held = true,
+            Action::Suppress => outcome.suppressed = true,
+        }
+    }
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::rules::default_rules;
+
+    const KP_MAP: u32 = 1339;
+    const OTHER_KP_MAP: u32 = 1351;
+    const HUB_MAP: u32 = 1154;
+    const NORMAL_MAP: u32 = 15;
+
+    struct Simulation {
+        rules: Vec<Rule>,
+        matching: Vec<usize>,
+        held: bool,
+        scheduled_refresh: Option<ScheduledRefresh>,
+        refreshes: u32,
+    }
+
+    impl Simulation {
+        fn new(rules: Vec<Rule>) -> Self {
+            Self {
+                rules,
+                matching: vec![],
+                held: false,
+                scheduled_refresh: None,
+                refreshes: 0,
+            }
+        }
+
+        fn enter(&mut self, map_id: u32, map_category: MapCategory) {
+            let input = RuleInput {
+                map_id,
+                map_category,
+                time: NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+                account: "Player.1234".to_string(),
+                character: None,
+                session_minutes: None,
+                in_combat: false,
+            };
+            let matching = matching_rules(&self.rules, &input);
+            let outcome = evaluate(&self.rules, &matching, &self.matching);
+            self.matching = matching;
+            self.held = outcome.held;
+            self.scheduled_refresh =
+                outcome.scheduled_refresh(self.scheduled_refresh.take(), Utc::now());
+            if released(&self.scheduled_refresh, self.held) {
+                self.scheduled_refresh = None;
+                self.refreshes += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn kp_map_then_hub_holds_until_normal_map() {
+        let mut simulation = Simulation::new(default_rules());
+        simulation.enter(KP_MAP, MapCategory::Kp);
+        assert_eq!(
+            simulation.scheduled_refresh,
+            Some(ScheduledRefresh::OnNormalMapEnter)
+        );
+        assert!(simulation.held);
+        simulation.enter(HUB_MAP, MapCategory::RetainRefresh);
+        assert_eq!(
+            simulation.scheduled_refresh,
+            Some(ScheduledRefresh::OnNormalMapEnter)
+        );
+        assert!(simulation.held);
+        assert_eq!(simulation.refreshes, 0);
+        simulation.enter(NORMAL_MAP, MapCategory::Other);
+        assert_eq!(simulation.scheduled_refresh, None);
+        assert_eq!(simulation.refreshes, 1);
+    }
+
+    #[test]
+    fn normal_map_alone_does_not_refresh() {
+        let mut simulation = Simulation::new(default_rules());
+        simulation.enter(NORMAL_MAP, MapCategory::Other);
+        simulation.enter(HUB_MAP, MapCategory::RetainRefresh);
+        simulation.enter(NORMAL_MAP, MapCategory::Other);
+        assert_eq!(simulation.scheduled_refresh, None);
+        assert_eq!(simulation.refreshes, 0);
+    }
+
+    #[test]
+    fn moving_between_kp_maps_does_not_reschedule() {
+        let mut simulation = Simulation::new(default_rules());
+        simulation.enter(KP_MAP, MapCategory::Kp);
+        let cooldown = ScheduledRefresh::OnTime(Utc::now() + TimeDelta::minutes(5));
+        simulation.scheduled_refresh = Some(cooldown.clone());
+        simulation.enter(OTHER_KP_MAP, MapCategory::Kp);
+        assert_eq!(simulation.scheduled_refresh, Some(cooldown));
+        assert_eq!(simulation.refreshes, 0);
+    }
+
+    #[test]
+    fn suppress_clears_scheduled_refresh() {
+        let mut rules = default_rules();
+        rules.push(Rule {
+            name: "No refresh in hub".to_string(),
+            enabled: true,
+            conditions: vec![Condition::MapIn {
+                map_ids: vec![HUB_MAP],
+            }],
+            action: Action::Suppress,
+        });
+        let mut simulation = Simulation::new(rules);
+        simulation.enter(KP_MAP, MapCategory::Kp);
+        simulation.enter(HUB_MAP, MapCategory::RetainRefresh);
+        assert_eq!(simulation.scheduled_refresh, None);
+        simulation.enter(NORMAL_MAP, MapCategory::Other);
+        assert_eq!(simulation.refreshes, 0);
+    }
+}
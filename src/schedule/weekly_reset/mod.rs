@@ -0,0 +1,70 @@
+use chrono::{DateTime, Datelike, NaiveTime, TimeDelta, Utc};
+
+const RESET_HOUR: u32 = 7;
+const RESET_MINUTE: u32 = 30;
+
+pub fn last_weekly_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    let reset_time = NaiveTime::from_hms_opt(RESET_HOUR, RESET_MINUTE, 0).unwrap();
+    let days_since_monday = now.weekday().num_days_from_monday() as i64;
+    let monday = now.date_naive() - TimeDelta::days(days_since_monday);
+    let reset = monday.and_time(reset_time).and_utc();
+    if reset > now {
+        reset - TimeDelta::weeks(1)
+    } else {
+        reset
+    }
+}
+
+pub fn next_weekly_reset(now: DateTime<Utc>) -> DateTime<Utc> {
+    last_weekly_reset(now) + TimeDelta::weeks(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn reset_at_monday_reset_time() {
+        let now = date(6, 7, 30);
+        assert_eq!(last_weekly_reset(now), now);
+        assert_eq!(next_weekly_reset(now), date(13, 7, 30));
+    }
+
+    #[test]
+    fn minute_before_reset_belongs_to_previous_week() {
+        let now = date(6, 7, 29);
+        assert_eq!(
+            last_weekly_reset(now),
+            Utc.with_ymd_and_hms(2024, 4, 29, 7, 30, 0).unwrap()
+        );
+        assert_eq!(next_weekly_reset(now), date(6, 7, 30));
+    }
+
+    #[test]
+    fn minute_after_reset_belongs_to_new_week() {
+        let now = date(6, 7, 31);
+        assert_eq!(last_weekly_reset(now), date(6, 7, 30));
+        assert_eq!(next_weekly_reset(now), date(13, 7, 30));
+    }
+
+    #[test]
+    fn sunday_resets_next_day() {
+        let now = date(5, 23, 59);
+        assert_eq!(
+            last_weekly_reset(now),
+            Utc.with_ymd_and_hms(2024, 4, 29, 7, 30, 0).unwrap()
+        );
+        assert_eq!(next_weekly_reset(now), date(6, 7, 30));
+    }
+
+    #[test]
+    fn monday_before_reset_time() {
+        let now = date(6, 0, 0);
+        assert_eq!(next_weekly_reset(now), date(6, 7, 30));
+    }
+}
@@ -0,0 +1,353 @@
+use crate::addon::Addon;
+use crate::api::kp::proofs::fetch_proofs;
+use crate::config::config_dir;
+use crate::storage::{load_json, save_json, take_loaded, BACKUP_COUNT};
+use chrono::{DateTime, TimeDelta, Utc};
+use function_name::named;
+use log::info;
+use nexus::alert::send_alert;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread;
+
+const MAX_STORED_SESSIONS: usize = 20;
+
+const RAID_WINGS: &[(u32, u8)] = &[
+    (1062, 1),
+    (1149, 2),
+    (1156, 3),
+    (1188, 4),
+    (1264, 5),
+    (1303, 6),
+    (1323, 7),
+    (1564, 8),
+];
+
+const PROOF_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("Legendary Insight", "LI"),
+    ("Legendary Divination", "LD"),
+    ("Unstable Fractal Essence", "UFE"),
+    ("Boneskinner Ritual Vial", "BRV"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RaidSession {
+    pub start_date: DateTime<Utc>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub map_visits: Vec<MapVisit>,
+    pub kills: Vec<String>,
+    pub refresh_attempts: u32,
+    pub refreshed: bool,
+    pub kp_before: Option<HashMap<String, u32>>,
+    pub kp_after: Option<HashMap<String, u32>>,
+    #[serde(default)]
+    pub summary: String,
+    #[serde(skip)]
+    pub normal_map_since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MapVisit {
+    pub map_id: u32,
+    pub seconds: i64,
+}
+
+impl RaidSession {
+    pub fn start(map_id: u32) -> Self {
+        Self {
+            start_date: Utc::now(),
+            end_date: None,
+            map_visits: vec![MapVisit { map_id, seconds: 0 }],
+            kills: vec![],
+            refresh_attempts: 0,
+            refreshed: false,
+            kp_before: None,
+            kp_after: None,
+            summary: "".to_string(),
+            normal_map_since: None,
+        }
+    }
+
+    pub fn add_map_time(&mut self, map_id: u32, seconds: i64) {
+        match self
+            .map_visits
+            .iter_mut()
+            .find(|visit| visit.map_id == map_id)
+        {
+            Some(visit) => visit.seconds += seconds,
+            None => self.map_visits.push(MapVisit { map_id, seconds }),
+        }
+    }
+
+    pub fn duration(&self) -> TimeDelta {
+        self.end_date
+            .unwrap_or_else(Utc::now)
+            .signed_duration_since(self.start_date)
+    }
+
+    pub fn summary_text(&self, kp_map_ids: &[u32], map_names: &HashMap<String, String>) -> String {
+        let mut parts = vec![
+            self.maps_text(kp_map_ids, map_names),
+            duration_text(self.duration()),
+        ];
+        if let Some(kp_diff) = self.kp_diff_text() {
+            parts.push(kp_diff);
+        }
+        parts.push(
+            if self.refreshed {
+                "KP refreshed"
+            } else if self.refresh_attempts > 0 {
+                "KP refresh failed"
+            } else {
+                "KP not refreshed"
+            }
+            .to_string(),
+        );
+        format!("Session: {}", parts.join(", "))
+    }
+
+    fn maps_text(&self, kp_map_ids: &[u32], map_names: &HashMap<String, String>) -> String {
+        let mut wings: Vec<u8> = Vec::new();
+        let mut other_maps: Vec<String> = Vec::new();
+        for visit in self
+            .map_visits
+            .iter()
+            .filter(|visit| kp_map_ids.contains(&visit.map_id))
+        {
+            match wing(visit.map_id) {
+                Some(wing) => {
+                    if !wings.contains(&wing) {
+                        wings.push(wing);
+                    }
+                }
+                None => {
+                    let map_id_str = visit.map_id.to_string();
+                    let name = map_names.get(&map_id_str).unwrap_or(&map_id_str).clone();
+                    if !other_maps.contains(&name) {
+                        other_maps.push(name);
+                    }
+                }
+            }
+        }
+        wings.sort();
+        let mut maps = Vec::new();
+        match (wings.first(), wings.last()) {
+            (Some(first), Some(last))
+                if wings.len() > 1 && (last - first) as usize == wings.len() - 1 =>
+            {
+                maps.push(format!("W{}–W{}", first, last))
+            }
+            _ => maps.extend(wings.iter().map(|wing| format!("W{}", wing))),
+        }
+        maps.extend(other_maps);
+        if maps.is_empty() {
+            "no KP maps".to_string()
+        } else {
+            maps.join(", ")
+        }
+    }
+
+    fn kp_diff_text(&self) -> Option<String> {
+        let before = self.kp_before.as_ref()?;
+        let after = self.kp_after.as_ref()?;
+        let mut gains: Vec<(String, u32)> = after
+            .iter()
+            .filter_map(|(name, amount)| {
+                let gained = amount.saturating_sub(*before.get(name).unwrap_or(&0));
+                (gained > 0).then(|| (abbreviation(name), gained))
+            })
+            .collect();
+        gains.sort();
+        if gains.is_empty() {
+            Some("no new KP".to_string())
+        } else {
+            Some(
+                gains
+                    .iter()
+                    .map(|(name, gained)| format!("+{} {}", gained, name))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            )
+        }
+    }
+}
+
+fn wing(map_id: u32) -> Option<u8> {
+    RAID_WINGS
+        .iter()
+        .find(|(id, _)| *id == map_id)
+        .map(|(_, wing)| *wing)
+}
+
+fn abbreviation(name: &str) -> String {
+    PROOF_ABBREVIATIONS
+        .iter()
+        .find(|(full_name, _)| *full_name == name)
+        .map_or_else(|| name.to_string(), |(_, short)| short.to_string())
+}
+
+pub fn duration_text(delta: TimeDelta) -> String {
+    let hours = delta.num_hours();
+    let minutes = delta.num_minutes() % 60;
+    if hours > 0 {
+        format!("{}h{:02}m", hours, minutes)
+    } else {
+        format!("{}m", minutes)
+    }
+}
+
+#[named]
+pub fn fetch_session_kp_before_thread() {
+    Addon::threads().push(thread::spawn(|| {
+        let kp_id = Addon::lock().config.kp_identifiers.main_id.clone();
+        let proofs = fetch_proofs(&kp_id);
+        if let Some(session) = Addon::lock().context.session.as_mut() {
+            session.kp_before = proofs;
+        }
+        info!("[{}] ended", function_name!());
+    }));
+}
+
+#[named]
+pub fn finish_session_thread(mut session: RaidSession) {
+    Addon::threads().push(thread::spawn(move || {
+        if session.refreshed {
+            let kp_id = Addon::lock().config.kp_identifiers.main_id.clone();
+            session.kp_after = fetch_proofs(&kp_id);
+        }
+        let mut addon = Addon::lock();
+        session.summary =
+            session.summary_text(&addon.config.kp_map_ids, &addon.context.ui.map_names);
+        info!("[{}] {}", function_name!(), session.summary);
+        if addon.config.notifications.notify_session_summary {
+            send_alert(&session.summary);
+        }
+        addon.context.sessions.insert(0, session);
+        addon.context.sessions.truncate(MAX_STORED_SESSIONS);
+        save_sessions(&addon.context.sessions);
+    }));
+}
+
+pub fn load_sessions() -> Vec<RaidSession> {
+    take_loaded(load_json(&sessions_file(), BACKUP_COUNT), "Sessions").unwrap_or_default()
+}
+
+fn save_sessions(sessions: &[RaidSession]) {
+    if let Err(err) = save_json(&sessions_file(), &sessions, BACKUP_COUNT) {
+        log::error!("Failed to save sessions: {err}");
+    }
+}
+
+fn sessions_file() -> PathBuf {
+    config_dir().join("sessions.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const KP_MAP_IDS: &[u32] = &[1062, 1149, 1156, 1188, 1303, 1432];
+
+    fn session(map_ids: &[u32], minutes: i64) -> RaidSession {
+        let start_date = Utc.with_ymd_and_hms(2024, 5, 7, 19, 0, 0).unwrap();
+        RaidSession {
+            start_date,
+            end_date: Some(start_date + TimeDelta::minutes(minutes)),
+            map_visits: map_ids
+                .iter()
+                .map(|map_id| MapVisit {
+                    map_id: *map_id,
+                    seconds: 60,
+                })
+                .collect(),
+            ..RaidSession::start(0)
+        }
+    }
+
+    fn proofs(entries: &[(&str, u32)]) -> Option<HashMap<String, u32>> {
+        Some(
+            entries
+                .iter()
+                .map(|(name, amount)| (name.to_string(), *amount))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn summary_of_refreshed_session() {
+        let mut session = session(&[1062, 1149, 1156, 1188], 130);
+        session.refreshed = true;
+        session.kp_before = proofs(&[("Legendary Insight", 10)]);
+        session.kp_after = proofs(&[("Legendary Insight", 18)]);
+        assert_eq!(
+            session.summary_text(KP_MAP_IDS, &HashMap::new()),
+            "Session: W1–W4, 2h10m, +8 LI, KP refreshed"
+        );
+    }
+
+    #[test]
+    fn summary_without_kp_data() {
+        let mut session = session(&[1062], 45);
+        assert_eq!(
+            session.summary_text(KP_MAP_IDS, &HashMap::new()),
+            "Session: W1, 45m, KP not refreshed"
+        );
+        session.refresh_attempts = 2;
+        session.kp_before = proofs(&[("Legendary Insight", 10)]);
+        assert_eq!(
+            session.summary_text(KP_MAP_IDS, &HashMap::new()),
+            "Session: W1, 45m, KP refresh failed"
+        );
+    }
+
+    #[test]
+    fn kp_diff_lists_gains_only() {
+        let mut session = session(&[], 0);
+        session.kp_before = proofs(&[("Legendary Insight", 10), ("Legendary Divination", 3)]);
+        session.kp_after = proofs(&[
+            ("Legendary Insight", 4),
+            ("Legendary Divination", 5),
+            ("Boneskinner Ritual Vial", 1),
+            ("Cerus Tribute", 2),
+        ]);
+        assert_eq!(
+            session.kp_diff_text(),
+            Some("+1 BRV, +2 Cerus Tribute, +2 LD".to_string())
+        );
+    }
+
+    #[test]
+    fn negative_kp_diff_is_no_new_kp() {
+        let mut session = session(&[], 0);
+        session.kp_before = proofs(&[("Legendary Insight", 10)]);
+        session.kp_after = proofs(&[("Legendary Insight", 2)]);
+        assert_eq!(session.kp_diff_text(), Some("no new KP".to_string()));
+    }
+
+    #[test]
+    fn maps_text_names_wings_and_other_maps() {
+        let map_names = HashMap::from([("1432".to_string(), "Strike Mission".to_string())]);
+        assert_eq!(
+            session(&[1188, 1062, 1303, 1432, 15], 0).maps_text(KP_MAP_IDS, &map_names),
+            "W1, W4, W6, Strike Mission"
+        );
+        assert_eq!(
+            session(&[1432], 0).maps_text(KP_MAP_IDS, &HashMap::new()),
+            "1432"
+        );
+        assert_eq!(
+            session(&[15], 0).maps_text(KP_MAP_IDS, &HashMap::new()),
+            "no KP maps"
+        );
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(duration_text(TimeDelta::seconds(30)), "0m");
+        assert_eq!(duration_text(TimeDelta::minutes(59)), "59m");
+        assert_eq!(duration_text(TimeDelta::minutes(60)), "1h00m");
+        assert_eq!(duration_text(TimeDelta::minutes(130)), "2h10m");
+    }
+}
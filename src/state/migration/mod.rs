@@ -0,0 +1,73 @@
+use crate::addon::VERSION;
+use crate::config::migration::Migration;
+use serde_json::Value;
+
+pub const STATE_MIGRATIONS: &[Migration] = &[Migration {
+    id: "linked_ids_from_kp_identifiers",
+    version: VERSION,
+    description: "move linked ids out of kp_identifiers",
+    apply: move_linked_ids,
+}];
+
+fn move_linked_ids(state: &mut Value) -> bool {
+    let Some(object) = state.as_object_mut() else {
+        return false;
+    };
+    if object.contains_key("linked_ids") {
+        return false;
+    }
+    let Some(linked_ids) = object
+        .get_mut("kp_identifiers")
+        .and_then(|identifiers| identifiers.as_object_mut())
+        .and_then(|identifiers| identifiers.remove("linked_ids"))
+    else {
+        return false;
+    };
+    object.insert("linked_ids".to_string(), linked_ids);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::migration::migrate_with;
+    use crate::state::State;
+    use serde_json::json;
+
+    #[test]
+    fn moves_linked_ids_from_legacy_config() {
+        let mut value = json!({
+            "version": "1.3.1",
+            "kp_identifiers": { "main_id": "Player.1234", "linked_ids": ["Alt.5678"] },
+            "refresh_on_next_load": true
+        });
+        let report = migrate_with(&mut value, STATE_MIGRATIONS, false);
+        assert_eq!(report.applied, vec!["linked_ids_from_kp_identifiers"]);
+        let state: State = serde_json::from_value(value).unwrap();
+        assert_eq!(state.linked_ids, Some(vec!["Alt.5678".to_string()]));
+        assert!(state.refresh_on_next_load);
+        assert_eq!(state.version, VERSION);
+    }
+
+    #[test]
+    fn keeps_current_state_unchanged() {
+        let mut value = serde_json::to_value(State {
+            linked_ids: Some(vec!["Alt.5678".to_string()]),
+            ..State::default()
+        })
+        .unwrap();
+        let report = migrate_with(&mut value, STATE_MIGRATIONS, false);
+        assert!(!report.changed());
+    }
+
+    #[test]
+    fn skips_state_saved_by_current_version() {
+        let mut value = json!({
+            "version": VERSION,
+            "kp_identifiers": { "linked_ids": ["Alt.5678"] }
+        });
+        let report = migrate_with(&mut value, STATE_MIGRATIONS, false);
+        assert!(!report.changed());
+        assert!(value.get("linked_ids").is_none());
+    }
+}
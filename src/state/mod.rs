@@ -0,0 +1,104 @@
+pub mod migration;
+pub mod pending_refresh;
+pub mod scheduling_pause;
+pub mod sync_state;
+
+use crate::addon::VERSION;
+use crate::config::config_dir;
+use crate::config::migration::migrate_with;
+use crate::config::presets::Preset;
+use crate::config::Config;
+use crate::state::migration::STATE_MIGRATIONS;
+use crate::state::pending_refresh::PendingRefresh;
+use crate::state::scheduling_pause::SchedulingPause;
+use crate::state::sync_state::SyncState;
+use crate::storage::{load_json_with, save_json, take_loaded, LoadResult, BACKUP_COUNT};
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct State {
+    pub version: String,
+    pub last_refresh_date: Option<DateTime<Utc>>,
+    pub refresh_on_next_load: bool,
+    pub linked_ids: Option<Vec<String>>,
+    pub last_pre_reset_refresh_date: Option<DateTime<Utc>>,
+    pub last_post_reset_refresh_date: Option<DateTime<Utc>>,
+    pub scheduling_pause: Option<SchedulingPause>,
+    pub refresh_missed_while_paused: bool,
+    pub pending_refresh: Option<PendingRefresh>,
+    pub active_preset: Option<String>,
+    pub preset_base: Option<Preset>,
+    pub sync: SyncState,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            version: VERSION.to_string(),
+            last_refresh_date: None,
+            refresh_on_next_load: false,
+            linked_ids: None,
+            last_pre_reset_refresh_date: None,
+            last_post_reset_refresh_date: None,
+            scheduling_pause: None,
+            refresh_missed_while_paused: false,
+            pending_refresh: None,
+            active_preset: None,
+            preset_base: None,
+            sync: SyncState::default(),
+        }
+    }
+}
+
+impl State {
+    pub fn try_load_legacy() -> Option<Self> {
+        let file = File::open(Config::file()).ok()?;
+        let value: Value = serde_json::from_reader(BufReader::new(file))
+            .inspect_err(|err| log::warn!("Failed to parse legacy state: {err}"))
+            .ok()?;
+        let state = parse_state(value)
+            .inspect_err(|err| log::warn!("Failed to parse legacy state: {err}"))
+            .ok()?;
+        info!("Migrated state from legacy config");
+        Some(state)
+    }
+
+    pub fn load() -> Self {
+        match load_json_with(&Self::file(), BACKUP_COUNT, parse_state) {
+            LoadResult::Missing => Self::try_load_legacy().unwrap_or_default(),
+            result => take_loaded(result, "State").unwrap_or_default(),
+        }
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = save_json(&Self::file(), self, BACKUP_COUNT) {
+            log::error!("Failed to save state: {err}");
+        }
+    }
+
+    pub fn file() -> PathBuf {
+        config_dir().join("state.json")
+    }
+}
+
+fn parse_state(mut value: Value) -> Result<State, String> {
+    let report = migrate_with(&mut value, STATE_MIGRATIONS, false);
+    for id in &report.applied {
+        let description = STATE_MIGRATIONS
+            .iter()
+            .find(|migration| migration.id == *id)
+            .map_or(*id, |migration| migration.description);
+        info!(
+            "State migrated from {:?}: {}",
+            report.from_version, description
+        );
+    }
+    serde_json::from_value(value).map_err(|err| err.to_string())
+}
@@ -0,0 +1,8 @@
+use crate::context::scheduled_refresh::ScheduledRefresh;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PendingRefresh {
+    pub refresh: ScheduledRefresh,
+    pub account: String,
+}
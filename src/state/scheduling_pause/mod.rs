@@ -0,0 +1,36 @@
+use crate::render::countdown_str;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SchedulingPause {
+    Until { date: DateTime<Utc> },
+    NextGameStart,
+    UntilResumed,
+}
+
+impl SchedulingPause {
+    pub fn is_active(&self) -> bool {
+        match self {
+            SchedulingPause::Until { date } => *date > Utc::now(),
+            SchedulingPause::NextGameStart | SchedulingPause::UntilResumed => true,
+        }
+    }
+}
+
+impl fmt::Display for SchedulingPause {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchedulingPause::Until { date } => write!(
+                f,
+                "paused until {} ({} left)",
+                date.with_timezone(&Local).format("%H:%M"),
+                countdown_str(date.signed_duration_since(Utc::now()))
+            ),
+            SchedulingPause::NextGameStart => write!(f, "paused until next game start"),
+            SchedulingPause::UntilResumed => write!(f, "paused until resumed"),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct SyncState {
+    pub field_dates: BTreeMap<String, DateTime<Utc>>,
+    pub field_values: BTreeMap<String, Value>,
+    pub last_sync_date: Option<DateTime<Utc>>,
+}
@@ -0,0 +1,171 @@
+use chrono::Local;
+use log::{error, info, warn};
+use nexus::alert::send_alert;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const BACKUP_COUNT: usize = 3;
+
+pub enum LoadResult<T> {
+    Loaded(T),
+    Recovered {
+        value: T,
+        backup: PathBuf,
+        broken: Option<PathBuf>,
+    },
+    Missing,
+    Unrecoverable {
+        broken: Option<PathBuf>,
+    },
+}
+
+pub fn save_json<T: Serialize>(path: &Path, value: &T, backups: usize) -> std::io::Result<()> {
+    let temp_path = with_suffix(path, "tmp");
+    {
+        let mut writer = BufWriter::new(File::create(&temp_path)?);
+        serde_json::to_writer_pretty(&mut writer, value)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+    }
+    if backups > 0 && path.exists() {
+        rotate_backups(path, backups);
+    }
+    fs::rename(&temp_path, path)
+}
+
+pub fn load_json<T: DeserializeOwned>(path: &Path, backups: usize) -> LoadResult<T> {
+    load_json_with(path, backups, |value| {
+        serde_json::from_value(value).map_err(|err| err.to_string())
+    })
+}
+
+// a broken file is moved aside rather than deleted, so the next save does not overwrite it
+pub fn load_json_with<T>(
+    path: &Path,
+    backups: usize,
+    parse: impl Fn(Value) -> Result<T, String>,
+) -> LoadResult<T> {
+    if !path.exists() {
+        return LoadResult::Missing;
+    }
+    match read_json(path, &parse) {
+        Ok(value) => {
+            info!("Loaded \"{}\"", path.display());
+            return LoadResult::Loaded(value);
+        }
+        Err(err) => warn!("Failed to parse \"{}\": {err}", path.display()),
+    }
+
+    let broken = keep_broken_file(path);
+    for index in 1..=backups {
+        let backup = backup_path(path, index);
+        if !backup.exists() {
+            continue;
+        }
+        match read_json(&backup, &parse) {
+            Ok(value) => {
+                info!(
+                    "Recovered \"{}\" from \"{}\"",
+                    path.display(),
+                    backup.display()
+                );
+                return LoadResult::Recovered {
+                    value,
+                    backup,
+                    broken,
+                };
+            }
+            Err(err) => warn!("Failed to parse backup \"{}\": {err}", backup.display()),
+        }
+    }
+    LoadResult::Unrecoverable { broken }
+}
+
+pub fn take_loaded<T>(result: LoadResult<T>, name: &str) -> Option<T> {
+    match result {
+        LoadResult::Loaded(value) => Some(value),
+        LoadResult::Recovered {
+            value,
+            backup,
+            broken,
+        } => {
+            send_alert(format!(
+                "{} file was corrupted, restored from backup {}{}",
+                name,
+                file_name(&backup),
+                broken_file_text(&broken)
+            ));
+            Some(value)
+        }
+        LoadResult::Missing => None,
+        LoadResult::Unrecoverable { broken } => {
+            send_alert(format!(
+                "{} file was corrupted and no backup could be read, defaults are used{}",
+                name,
+                broken_file_text(&broken)
+            ));
+            None
+        }
+    }
+}
+
+fn broken_file_text(broken: &Option<PathBuf>) -> String {
+    broken
+        .as_ref()
+        .map(|broken| format!(" (broken file kept as {})", file_name(broken)))
+        .unwrap_or_default()
+}
+
+fn read_json<T>(path: &Path, parse: &impl Fn(Value) -> Result<T, String>) -> Result<T, String> {
+    let file = File::open(path).map_err(|err| err.to_string())?;
+    let value = serde_json::from_reader(BufReader::new(file)).map_err(|err| err.to_string())?;
+    parse(value)
+}
+
+fn rotate_backups(path: &Path, backups: usize) {
+    for index in (1..backups).rev() {
+        let from = backup_path(path, index);
+        if from.exists() {
+            let _ = fs::rename(&from, backup_path(path, index + 1));
+        }
+    }
+    if let Err(err) = fs::copy(path, backup_path(path, 1)) {
+        error!("Failed to back up \"{}\": {err}", path.display());
+    }
+}
+
+fn keep_broken_file(path: &Path) -> Option<PathBuf> {
+    let broken = with_suffix(
+        path,
+        &format!("broken-{}", Local::now().format("%Y%m%d-%H%M%S")),
+    );
+    match fs::rename(path, &broken) {
+        Ok(_) => Some(broken),
+        Err(err) => {
+            error!("Failed to move aside \"{}\": {err}", path.display());
+            None
+        }
+    }
+}
+
+pub fn backup_path(path: &Path, index: usize) -> PathBuf {
+    with_suffix(path, &format!("bak{}", index))
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".");
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
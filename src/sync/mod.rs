@@ -0,0 +1,327 @@
+use crate::addon::Addon;
+use crate::config::Config;
+use crate::state::sync_state::SyncState;
+use crate::state::State;
+use crate::storage::save_json;
+use ::function_name::named;
+use chrono::{DateTime, Utc};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::sync::MutexGuard;
+
+const SYNC_FILE_NAME: &str = "kp_sync_shared.json";
+
+const CONFIG_FIELDS: &[&str] = &[
+    "kp_map_ids",
+    "retain_refresh_map_ids",
+    "notifications",
+    "scheduling_rules_enabled",
+    "refresh_only_on_kill",
+    "squad_triggers",
+    "weekly_reset",
+    "calendar",
+    "rules",
+    "presets",
+];
+
+const STATE_FIELDS: &[&str] = &[
+    "last_refresh_date",
+    "pending_refresh",
+    "last_pre_reset_refresh_date",
+    "last_post_reset_refresh_date",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct SyncedField {
+    value: Value,
+    modified: DateTime<Utc>,
+    machine: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+struct SyncFile {
+    fields: BTreeMap<String, SyncedField>,
+}
+
+fn sync_file(addon: &Addon) -> Option<PathBuf> {
+    let sync = &addon.config.folder_sync;
+    if !sync.enabled || sync.directory.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(&sync.directory).join(SYNC_FILE_NAME))
+}
+
+fn machine_name() -> String {
+    env::var("COMPUTERNAME").unwrap_or_else(|_| "unknown".to_string())
+}
+
+fn read_sync_file(path: &PathBuf) -> Option<SyncFile> {
+    let file = File::open(path).ok()?;
+    serde_json::from_reader(BufReader::new(file))
+        .inspect_err(|err| warn!("Failed to parse sync file: {err}"))
+        .ok()
+}
+
+fn local_fields(config: &Config, state: &State) -> BTreeMap<String, Value> {
+    let mut fields = BTreeMap::new();
+    for (value, keys) in [
+        (serde_json::to_value(config), CONFIG_FIELDS),
+        (serde_json::to_value(state), STATE_FIELDS),
+    ] {
+        let Ok(Value::Object(object)) = value else {
+            continue;
+        };
+        for key in keys {
+            if let Some(field) = object.get(*key) {
+                fields.insert(key.to_string(), field.clone());
+            }
+        }
+    }
+    fields
+}
+
+fn apply_fields(
+    addon: &mut MutexGuard<Addon>,
+    fields: Vec<(String, Value)>,
+) -> Result<(), serde_json::Error> {
+    let mut config = object(serde_json::to_value(&addon.config)?);
+    let mut state = object(serde_json::to_value(&addon.state)?);
+    for (key, value) in fields {
+        if CONFIG_FIELDS.contains(&key.as_str()) {
+            config.insert(key, value);
+        } else if STATE_FIELDS.contains(&key.as_str()) {
+            state.insert(key, value);
+        }
+    }
+    let config: Config = serde_json::from_value(Value::Object(config))?;
+    let state: State = serde_json::from_value(Value::Object(state))?;
+    addon.config = config;
+    addon.state = state;
+    Ok(())
+}
+
+fn object(value: Value) -> Map<String, Value> {
+    match value {
+        Value::Object(object) => object,
+        _ => Map::new(),
+    }
+}
+
+fn track_local_changes(addon: &mut MutexGuard<Addon>) {
+    let now = Utc::now();
+    for (key, value) in local_fields(&addon.config, &addon.state) {
+        let sync = &mut addon.state.sync;
+        if sync.field_values.get(&key) != Some(&value) {
+            sync.field_dates.insert(key.clone(), now);
+            sync.field_values.insert(key, value);
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct FieldMerge {
+    updates: Vec<(String, Value)>,
+    conflicts: Vec<String>,
+}
+
+#[named]
+fn merge_fields(
+    local: &BTreeMap<String, Value>,
+    sync: &mut SyncState,
+    remote_fields: BTreeMap<String, SyncedField>,
+) -> FieldMerge {
+    let mut merge = FieldMerge::default();
+    for (key, remote) in remote_fields {
+        if !CONFIG_FIELDS.contains(&key.as_str()) && !STATE_FIELDS.contains(&key.as_str()) {
+            continue;
+        }
+        let local_date = sync.field_dates.get(&key);
+        if local_date.is_some_and(|date| *date >= remote.modified) {
+            continue;
+        }
+        let local_value = local.get(&key);
+        if local_value == Some(&remote.value) {
+            continue;
+        }
+        if local_date.is_some() && local_value != sync.field_values.get(&key) {
+            warn!(
+                "[{}] conflict on {}: change from {} at {} overrides unsynced local change",
+                function_name!(),
+                key,
+                remote.machine,
+                remote.modified
+            );
+            merge.conflicts.push(key.clone());
+        }
+        info!(
+            "[{}] {} updated from {} ({})",
+            function_name!(),
+            key,
+            remote.machine,
+            remote.modified
+        );
+        sync.field_dates.insert(key.clone(), remote.modified);
+        sync.field_values.insert(key.clone(), remote.value.clone());
+        merge.updates.push((key, remote.value));
+    }
+    merge
+}
+
+#[named]
+pub fn merge_from_sync_folder(addon: &mut MutexGuard<Addon>) {
+    let Some(path) = sync_file(addon) else {
+        return;
+    };
+    let Some(sync_file) = read_sync_file(&path) else {
+        return;
+    };
+    let local = local_fields(&addon.config, &addon.state);
+    let merge = merge_fields(&local, &mut addon.state.sync, sync_file.fields);
+    if merge.updates.is_empty() {
+        return;
+    }
+    if let Err(err) = apply_fields(addon, merge.updates) {
+        warn!(
+            "[{}] sync folder values rejected: {}",
+            function_name!(),
+            err
+        );
+    }
+}
+
+#[named]
+pub fn export_to_sync_folder(addon: &mut MutexGuard<Addon>) {
+    let Some(path) = sync_file(addon) else {
+        return;
+    };
+    track_local_changes(addon);
+    let mut sync_file = read_sync_file(&path).unwrap_or_default();
+    let machine = machine_name();
+    let mut changed = false;
+    for (key, value) in &addon.state.sync.field_values {
+        let Some(modified) = addon.state.sync.field_dates.get(key) else {
+            continue;
+        };
+        match sync_file.fields.get(key) {
+            Some(remote) if remote.modified >= *modified => {
+                if remote.value != *value {
+                    warn!(
+                        "[{}] conflict on {}: newer value from {} kept in sync folder",
+                        function_name!(),
+                        key,
+                        remote.machine
+                    );
+                }
+            }
+            _ => {
+                sync_file.fields.insert(
+                    key.clone(),
+                    SyncedField {
+                        value: value.clone(),
+                        modified: *modified,
+                        machine: machine.clone(),
+                    },
+                );
+                changed = true;
+            }
+        }
+    }
+    if !changed {
+        return;
+    }
+    match save_json(&path, &sync_file, 0) {
+        Ok(_) => addon.state.sync.last_sync_date = Some(Utc::now()),
+        Err(err) => warn!("[{}] failed to write sync file: {}", function_name!(), err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use serde_json::json;
+
+    fn date(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn remote(value: Value, modified: DateTime<Utc>) -> BTreeMap<String, SyncedField> {
+        BTreeMap::from([(
+            "kp_map_ids".to_string(),
+            SyncedField {
+                value,
+                modified,
+                machine: "other".to_string(),
+            },
+        )])
+    }
+
+    fn synced(value: Value, modified: DateTime<Utc>) -> SyncState {
+        SyncState {
+            field_dates: BTreeMap::from([("kp_map_ids".to_string(), modified)]),
+            field_values: BTreeMap::from([("kp_map_ids".to_string(), value)]),
+            last_sync_date: None,
+        }
+    }
+
+    fn local(value: Value) -> BTreeMap<String, Value> {
+        BTreeMap::from([("kp_map_ids".to_string(), value)])
+    }
+
+    #[test]
+    fn newer_remote_wins() {
+        let mut sync = synced(json!([1]), date(10));
+        let merge = merge_fields(&local(json!([1])), &mut sync, remote(json!([2]), date(11)));
+        assert_eq!(merge.updates, vec![("kp_map_ids".to_string(), json!([2]))]);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(sync.field_dates["kp_map_ids"], date(11));
+        assert_eq!(sync.field_values["kp_map_ids"], json!([2]));
+    }
+
+    #[test]
+    fn newer_local_is_kept() {
+        let mut sync = synced(json!([1]), date(12));
+        let merge = merge_fields(&local(json!([1])), &mut sync, remote(json!([2]), date(11)));
+        assert_eq!(merge, FieldMerge::default());
+        assert_eq!(sync, synced(json!([1]), date(12)));
+    }
+
+    #[test]
+    fn unsynced_local_change_is_reported_as_conflict() {
+        let mut sync = synced(json!([1]), date(10));
+        let merge = merge_fields(&local(json!([3])), &mut sync, remote(json!([2]), date(11)));
+        assert_eq!(merge.updates, vec![("kp_map_ids".to_string(), json!([2]))]);
+        assert_eq!(merge.conflicts, vec!["kp_map_ids"]);
+    }
+
+    #[test]
+    fn first_sync_takes_remote_values() {
+        let mut sync = SyncState::default();
+        let merge = merge_fields(&local(json!([1])), &mut sync, remote(json!([2]), date(11)));
+        assert_eq!(merge.updates, vec![("kp_map_ids".to_string(), json!([2]))]);
+        assert!(merge.conflicts.is_empty());
+        assert_eq!(sync.field_dates["kp_map_ids"], date(11));
+    }
+
+    #[test]
+    fn unknown_and_equal_fields_are_skipped() {
+        let mut sync = SyncState::default();
+        let mut fields = remote(json!([1]), date(11));
+        fields.insert(
+            "api_key".to_string(),
+            SyncedField {
+                value: json!("secret"),
+                modified: date(11),
+                machine: "other".to_string(),
+            },
+        );
+        let merge = merge_fields(&local(json!([1])), &mut sync, fields);
+        assert_eq!(merge, FieldMerge::default());
+    }
+}
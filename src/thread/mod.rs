@@ -1,18 +1,31 @@
 use crate::addon::Addon;
+use crate::api::apply_network_settings;
 use crate::api::kp::refresh::refresh_kp_thread;
+use crate::config::reload::save_config_if_changed;
+use crate::config::rules::MapCategory;
 use crate::context::scheduled_refresh::ScheduledRefresh;
-use chrono::Local;
+use crate::evtc::watcher::poll_evtc_logs;
+use crate::lease::{acquire_lease, lease_dir, LeaseStatus, MAIN_LEASE_TTL_SEC};
+use crate::schedule::calendar::next_refresh_date;
+use crate::schedule::rules::{evaluate, matching_rules, released, RuleInput, RuleOutcome};
+use crate::schedule::weekly_reset::{last_weekly_reset, next_weekly_reset};
+use crate::session::{fetch_session_kp_before_thread, finish_session_thread, RaidSession};
+use crate::sync::export_to_sync_folder;
+use chrono::{Local, TimeDelta, Utc};
 use function_name::named;
 use log::{debug, error, info};
+use std::sync::MutexGuard;
 use std::thread;
 use std::time::Duration;
 use nexus::alert::send_alert;
 use nexus::data_link::rtapi::read_rtapi;
-use nexus::rtapi::PlayerData;
+use nexus::rtapi::{GroupType, PlayerData};
 
 const BACKGROUND_THREAD_SLEEP_DURATION_MS: u64 = 50;
 const CONFIG_SAVE_INTERVAL_SEC: u64 = 5;
 const REFRESH_DAEMON_INTERVAL_SEC: u64 = 1;
+const EVTC_POLL_INTERVAL_SEC: u64 = 5;
+const SESSION_END_GRACE_SEC: i64 = 10;
 
 pub fn background_thread() {
     Addon::threads().push(thread::spawn(|| loop {
@@ -28,13 +41,21 @@ pub fn background_thread() {
             > Addon::lock().context.last_config_save_date + Duration::from_secs(CONFIG_SAVE_INTERVAL_SEC)
         {
             let addon = &mut Addon::lock();
-            if addon.context.scheduled_refresh.is_some() {
-                addon.config.refresh_on_next_load = true;
-            }
+            addon.persist_scheduled_refresh();
 
-            if addon.context.saved_config.as_ref() != Some(&addon.config) {
-                addon.config.save();
-                addon.context.saved_config = Some(addon.config.clone());
+            let mut saved = false;
+            if save_config_if_changed(addon) {
+                apply_network_settings(&addon.config.network);
+                addon.validate_config();
+                saved = true;
+            }
+            if addon.context.saved_state.as_ref() != Some(&addon.state) {
+                addon.state.save();
+                addon.context.saved_state = Some(addon.state.clone());
+                saved = true;
+            }
+            if saved {
+                export_to_sync_folder(addon);
             }
 
             addon.context.last_config_save_date = now;
@@ -57,14 +78,30 @@ pub fn background_thread() {
                 Addon::lock().config.kp_identifiers.main_id = account_name.clone();
             }
             if !autodetect_account_name || !account_name.is_empty() {
+                coordinate_clients();
+                restore_pending_refresh();
+                resume_on_pause_end();
+                apply_rules();
                 refresh_on_load();
-                schedule_on_map_enter();
+                schedule_on_squad_change();
+                schedule_on_weekly_reset();
+                schedule_on_calendar();
                 refresh_on_schedule();
             }
+            let elapsed = now - Addon::lock().context.last_refresh_daemon_tick_date;
+            track_session(elapsed.num_seconds());
 
             Addon::lock().context.last_refresh_daemon_tick_date = now;
         }
 
+        if now
+            > Addon::lock().context.evtc_watcher.last_poll_date
+                + Duration::from_secs(EVTC_POLL_INTERVAL_SEC)
+        {
+            poll_evtc_logs();
+            Addon::lock().context.evtc_watcher.last_poll_date = now;
+        }
+
         thread::sleep(Duration::from_millis(BACKGROUND_THREAD_SLEEP_DURATION_MS));
     }));
 }
@@ -75,17 +112,16 @@ fn refresh_on_load() {
     if let Some(m) = addon.context.mumble {
         let current_map_id = &m.read_map_id();
         if current_map_id != &0 {
-            if addon.context.first_map_tick && addon.config.refresh_on_next_load {
+            if addon.context.first_map_tick && addon.state.refresh_on_next_load {
                 info!("[{}] refreshing / scheduling refresh", function_name!());
-                if (addon.config.kp_map_ids.contains(current_map_id)
-                    || addon.config.retain_refresh_map_ids.contains(current_map_id))
-                    && addon.config.scheduling_on_map_enter_enabled
-                {
+                if addon.context.refresh_held {
                     addon.context.scheduled_refresh = Some(ScheduledRefresh::OnNormalMapEnter);
+                } else if addon.scheduling_paused() {
+                    addon.state.refresh_missed_while_paused = true;
                 } else {
                     refresh_kp_thread();
                 }
-                addon.config.refresh_on_next_load = false;
+                addon.state.refresh_on_next_load = false;
             }
             addon.context.first_map_tick = false;
         }
@@ -96,51 +132,345 @@ fn refresh_on_load() {
 fn refresh_on_schedule() {
     let mut addon = Addon::lock();
     if let Some(ScheduledRefresh::OnTime(time)) = addon.context.scheduled_refresh {
-        if time < Local::now() {
-            info!("[{}] scheduled refresh executed", function_name!());
+        if time < Utc::now() {
             addon.context.scheduled_refresh = None;
+            if addon.scheduling_paused() {
+                info!("[{}] scheduled refresh postponed, paused", function_name!());
+                addon.state.refresh_missed_while_paused = true;
+                return;
+            }
+            info!("[{}] scheduled refresh executed", function_name!());
             refresh_kp_thread();
         }
     }
-    let mut retain_refresh = false;
+    if released(&addon.context.scheduled_refresh, addon.context.refresh_held) {
+        addon.context.scheduled_refresh = None;
+        if addon.config.refresh_only_on_kill && !addon.context.has_unrefreshed_kills() {
+            info!(
+                "[{}] map enter refresh skipped, no kill detected",
+                function_name!()
+            );
+            return;
+        }
+        if addon.scheduling_paused() {
+            info!("[{}] map enter refresh postponed, paused", function_name!());
+            addon.state.refresh_missed_while_paused = true;
+            return;
+        }
+        info!("[{}] map enter refresh executed", function_name!());
+        refresh_kp_thread();
+    }
+}
+
+#[named]
+fn coordinate_clients() {
+    let (main_id, instance_id, scheduled_refresh, last_refresh_date) = {
+        let addon = Addon::lock();
+        if !addon.config.valid() {
+            return;
+        }
+        (
+            addon.config.kp_identifiers.main_id.clone(),
+            addon.context.instance_id.clone(),
+            addon.context.scheduled_refresh.clone(),
+            addon.state.last_refresh_date,
+        )
+    };
+    // file locking may block, keep the addon unlocked so rendering is not held up
+    let status = acquire_lease(
+        &lease_dir(),
+        &main_id,
+        &instance_id,
+        TimeDelta::seconds(MAIN_LEASE_TTL_SEC),
+        scheduled_refresh,
+        last_refresh_date,
+    );
+    let mut addon = Addon::lock();
+    if addon.config.kp_identifiers.main_id != main_id {
+        return;
+    }
+    match status {
+        Ok(LeaseStatus::Acquired) => {
+            if addon.context.other_client_lease.take().is_some() {
+                info!("[{}] lease acquired", function_name!());
+            }
+        }
+        Ok(LeaseStatus::HeldBy(lease)) => {
+            if addon.context.other_client_lease.is_none() {
+                info!(
+                    "[{}] refresh handled by client {}",
+                    function_name!(),
+                    lease.owner
+                );
+            }
+            addon.context.other_client_lease = Some(lease);
+        }
+        Err(err) => debug!("[{}] lease unavailable: {}", function_name!(), err),
+    }
+}
+
+#[named]
+fn restore_pending_refresh() {
+    let mut addon = Addon::lock();
+    if addon.context.pending_refresh_restored {
+        return;
+    }
+    addon.context.pending_refresh_restored = true;
+    let Some(pending) = addon.state.pending_refresh.take() else {
+        return;
+    };
+    if pending.account != addon.config.kp_identifiers.main_id {
+        info!(
+            "[{}] pending refresh of {} dropped, account changed",
+            function_name!(),
+            pending.account
+        );
+        addon.state.refresh_on_next_load = false;
+        return;
+    }
+    if let ScheduledRefresh::OnTime(_) = pending.refresh {
+        info!(
+            "[{}] restored refresh scheduled {}",
+            function_name!(),
+            pending.refresh
+        );
+        addon.state.refresh_on_next_load = false;
+        addon.context.scheduled_refresh = Some(pending.refresh);
+    }
+}
+
+fn resume_on_pause_end() {
+    let mut addon = Addon::lock();
+    if addon.state.scheduling_pause.is_some() && !addon.scheduling_paused() {
+        addon.resume_scheduling();
+    }
+}
+
+#[named]
+fn apply_rules() {
+    let mut addon = Addon::lock();
     if let Some(m) = addon.context.mumble {
-        retain_refresh = addon
-            .config
-            .retain_refresh_map_ids
-            .contains(&m.read_map_id());
+        addon.context.identity = m.parse_identity().ok();
     }
+    if !addon.config.scheduling_rules_enabled {
+        addon.context.refresh_held = false;
+        addon.context.matching_rules.clear();
+        return;
+    }
+    let Some(input) = rule_input(&addon) else {
+        return;
+    };
+    let matching = matching_rules(&addon.config.rules, &input);
+    let outcome = evaluate(
+        &addon.config.rules,
+        &matching,
+        &addon.context.matching_rules,
+    );
+    addon.context.matching_rules = matching;
+    addon.context.refresh_held = outcome.held;
 
-    if !addon.context.on_kp_map
-        && !retain_refresh
-        && addon
+    let outcome = if addon.scheduling_enabled_for_character() {
+        outcome
+    } else {
+        debug!(
+            "[{}] scheduling disabled for current character",
+            function_name!()
+        );
+        RuleOutcome {
+            suppressed: outcome.suppressed,
+            ..RuleOutcome::default()
+        }
+    };
+    let current = addon.context.scheduled_refresh.clone();
+    let scheduled_refresh = outcome.scheduled_refresh(current.clone(), Utc::now());
+    if scheduled_refresh != current {
+        info!(
+            "[{}] scheduled refresh changed by rule: {:?}",
+            function_name!(),
+            scheduled_refresh
+        );
+        addon.context.scheduled_refresh = scheduled_refresh;
+    }
+}
+
+fn rule_input(addon: &MutexGuard<Addon>) -> Option<RuleInput> {
+    let map_id = addon.context.mumble?.read_map_id();
+    if map_id == 0 {
+        return None;
+    }
+    let map_category = if addon.config.kp_map_ids.contains(&map_id) {
+        MapCategory::Kp
+    } else if addon.config.retain_refresh_map_ids.contains(&map_id) {
+        MapCategory::RetainRefresh
+    } else {
+        MapCategory::Other
+    };
+    let account = if addon.context.detected_account_name.is_empty() {
+        addon.config.kp_identifiers.main_id.clone()
+    } else {
+        addon.context.detected_account_name.clone()
+    };
+    Some(RuleInput {
+        map_id,
+        map_category,
+        time: Local::now().time(),
+        account,
+        character: addon
             .context
-            .scheduled_refresh
+            .identity
             .as_ref()
-            .is_some_and(|sr| matches!(sr, ScheduledRefresh::OnNormalMapEnter))
-    {
-        info!("[{}] map enter refresh executed", function_name!());
-        addon.context.scheduled_refresh = None;
-        refresh_kp_thread();
-    }
+            .map(|identity| identity.name.clone()),
+        session_minutes: addon
+            .context
+            .session
+            .as_ref()
+            .map(|session| session.duration().num_minutes()),
+        in_combat: addon.context.in_combat,
+    })
 }
 
 #[named]
-fn schedule_on_map_enter() {
+fn schedule_on_squad_change() {
     let mut addon = Addon::lock();
-    if !addon.config.scheduling_on_map_enter_enabled {
+    if let Some(m) = addon.context.mumble {
+        if addon.config.kp_map_ids.contains(&m.read_map_id()) {
+            addon.context.kp_map_visited = true;
+        }
+    }
+    let (in_squad, member_count) = match &addon.context.rtapi {
+        Some(rtapi) => (
+            matches!(rtapi.group_type, GroupType::Squad | GroupType::RaidSquad),
+            rtapi.group_member_count,
+        ),
+        None => return,
+    };
+    let was_in_squad = addon.context.in_squad;
+    let previous_member_count = addon.context.squad_member_count;
+    addon.context.in_squad = in_squad;
+    addon.context.squad_member_count = member_count;
+
+    if !addon.context.kp_map_visited {
         return;
     }
-    match addon.context.mumble {
-        Some(m) => {
-            let previous_map_on_kp = addon.context.on_kp_map;
-            addon.context.on_kp_map = addon.config.kp_map_ids.contains(&m.read_map_id());
-            if !previous_map_on_kp && addon.context.on_kp_map {
-                info!("[{}] refresh on enter scheduled", function_name!());
-                addon.context.scheduled_refresh = Some(ScheduledRefresh::OnNormalMapEnter);
+    let triggers = &addon.config.squad_triggers;
+    let squad_left = triggers.refresh_on_squad_leave && was_in_squad && !in_squad;
+    let squad_size_dropped = triggers.refresh_on_squad_size_drop
+        && in_squad
+        && previous_member_count >= triggers.squad_size_threshold
+        && member_count < triggers.squad_size_threshold;
+    if (squad_left || squad_size_dropped) && addon.schedule_refresh(Utc::now()) {
+        info!(
+            "[{}] refresh scheduled (squad left: {}, squad size: {} -> {})",
+            function_name!(),
+            squad_left,
+            previous_member_count,
+            member_count
+        );
+        addon.context.kp_map_visited = false;
+    }
+}
+
+#[named]
+fn schedule_on_weekly_reset() {
+    let mut addon = Addon::lock();
+    let now = Utc::now();
+    let settings = addon.config.weekly_reset.clone();
+    if settings.refresh_before_reset {
+        let refresh_date =
+            next_weekly_reset(now) - TimeDelta::minutes(settings.minutes_before_reset as i64);
+        let refreshed = addon
+            .state
+            .last_pre_reset_refresh_date
+            .is_some_and(|date| date >= refresh_date);
+        if now >= refresh_date && !refreshed && addon.schedule_refresh(now) {
+            info!("[{}] pre-reset refresh scheduled", function_name!());
+            addon.state.last_pre_reset_refresh_date = Some(now);
+        }
+    }
+    if settings.refresh_after_first_session {
+        let last_reset = last_weekly_reset(now);
+        let refreshed = addon
+            .state
+            .last_post_reset_refresh_date
+            .is_some_and(|date| date >= last_reset);
+        let first_session = addon
+            .context
+            .sessions
+            .first()
+            .filter(|session| session.end_date.is_some_and(|date| date >= last_reset));
+        if let (Some(session), false) = (first_session, refreshed) {
+            if !session.refreshed {
+                if !addon.schedule_refresh(now) {
+                    return;
+                }
+                info!(
+                    "[{}] post-reset session refresh scheduled",
+                    function_name!()
+                );
+            }
+            addon.state.last_post_reset_refresh_date = Some(now);
+        }
+    }
+}
+
+#[named]
+fn schedule_on_calendar() {
+    let mut addon = Addon::lock();
+    let now = Local::now();
+    let last_check = addon.context.last_calendar_check_date;
+    if addon.config.calendar.enabled {
+        let due = next_refresh_date(&addon.config.calendar, last_check).filter(|date| *date <= now);
+        if let Some(date) = due {
+            if !addon.schedule_refresh(date.with_timezone(&Utc)) {
+                return;
             }
+            info!("[{}] calendar refresh scheduled", function_name!());
+        }
+    }
+    addon.context.last_calendar_check_date = now;
+}
+
+#[named]
+fn track_session(elapsed_seconds: i64) {
+    let mut addon = Addon::lock();
+    let Some(m) = addon.context.mumble else {
+        return;
+    };
+    let map_id = m.read_map_id();
+    if map_id == 0 {
+        return;
+    }
+    let on_kp_map = addon.config.kp_map_ids.contains(&map_id);
+    let on_retain_map = addon.config.retain_refresh_map_ids.contains(&map_id);
+    let refresh_pending =
+        addon.context.refresh_in_progress || addon.context.scheduled_refresh.is_some();
+
+    if addon.context.session.is_none() {
+        if on_kp_map {
+            info!("[{}] session started", function_name!());
+            addon.context.session = Some(RaidSession::start(map_id));
+            fetch_session_kp_before_thread();
         }
-        None => addon.context.on_kp_map = false,
+        return;
     }
+
+    let now = Utc::now();
+    let session = addon.context.session.as_mut().unwrap();
+    session.add_map_time(map_id, elapsed_seconds);
+    if on_kp_map || on_retain_map {
+        session.normal_map_since = None;
+        return;
+    }
+    let normal_map_since = *session.normal_map_since.get_or_insert(now);
+    if refresh_pending || now - normal_map_since < TimeDelta::seconds(SESSION_END_GRACE_SEC) {
+        return;
+    }
+
+    let mut session = addon.context.session.take().unwrap();
+    session.end_date = Some(now);
+    session.kills = addon.context.kill_names_since(session.start_date);
+    info!("[{}] session ended", function_name!());
+    finish_session_thread(session);
 }
 
 #[named]